@@ -0,0 +1,3 @@
+ALTER TABLE players DROP COLUMN upper_score;
+ALTER TABLE players DROP COLUMN bonus;
+ALTER TABLE players DROP COLUMN lower_score;
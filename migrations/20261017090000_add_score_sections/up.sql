@@ -0,0 +1,6 @@
+ALTER TABLE players ADD COLUMN upper_score INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE players ADD COLUMN bonus INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE players ADD COLUMN lower_score INTEGER NOT NULL DEFAULT 0;
+
+-- games started before this migration only know their total, keep it intact
+UPDATE players SET lower_score = score;
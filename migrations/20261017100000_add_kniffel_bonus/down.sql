@@ -0,0 +1,4 @@
+ALTER TABLE games DROP COLUMN kniffel_bonus_rule;
+
+ALTER TABLE players DROP COLUMN scored_kniffel;
+ALTER TABLE players DROP COLUMN kniffel_bonus_count;
@@ -0,0 +1,4 @@
+ALTER TABLE games ADD COLUMN kniffel_bonus_rule VARCHAR(255) NOT NULL DEFAULT 'KNIFFEL';
+
+ALTER TABLE players ADD COLUMN scored_kniffel BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE players ADD COLUMN kniffel_bonus_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+ALTER TABLE players ADD COLUMN seat INTEGER NOT NULL DEFAULT 0;
+
+-- existing games keep the order in which their players were inserted
+UPDATE players p
+    JOIN (SELECT id, ROW_NUMBER() OVER (PARTITION BY game_id ORDER BY id) - 1 AS seat_number FROM players) s
+    ON p.id = s.id
+SET p.seat = s.seat_number;
@@ -0,0 +1,2 @@
+ALTER TABLE games DROP COLUMN dice_seed;
+ALTER TABLE games DROP COLUMN dice_position;
@@ -0,0 +1,2 @@
+ALTER TABLE games ADD COLUMN dice_seed BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE games ADD COLUMN dice_position BIGINT NOT NULL DEFAULT 0;
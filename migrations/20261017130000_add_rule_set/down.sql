@@ -0,0 +1 @@
+ALTER TABLE games CHANGE COLUMN rule_set kniffel_bonus_rule VARCHAR(255) NOT NULL DEFAULT 'KNIFFEL';
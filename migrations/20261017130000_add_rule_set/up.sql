@@ -0,0 +1,2 @@
+-- the kniffel bonus rules KNIFFEL and YAHTZEE are now rule sets of the same name
+ALTER TABLE games CHANGE COLUMN kniffel_bonus_rule rule_set VARCHAR(255) NOT NULL DEFAULT 'KNIFFEL';
@@ -0,0 +1 @@
+ALTER TABLE players ADD COLUMN saved_rolls INTEGER NOT NULL DEFAULT 0;
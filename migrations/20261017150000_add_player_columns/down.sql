@@ -0,0 +1,18 @@
+ALTER TABLE games DROP COLUMN column_count;
+
+ALTER TABLE players ADD COLUMN used_booking_types VARCHAR(255) NOT NULL DEFAULT '' AFTER score;
+ALTER TABLE players ADD COLUMN upper_score INTEGER NOT NULL DEFAULT 0 AFTER used_booking_types;
+ALTER TABLE players ADD COLUMN bonus INTEGER NOT NULL DEFAULT 0 AFTER upper_score;
+ALTER TABLE players ADD COLUMN lower_score INTEGER NOT NULL DEFAULT 0 AFTER bonus;
+ALTER TABLE players ADD COLUMN scored_kniffel BOOLEAN NOT NULL DEFAULT FALSE AFTER lower_score;
+ALTER TABLE players ADD COLUMN kniffel_bonus_count INTEGER NOT NULL DEFAULT 0 AFTER scored_kniffel;
+
+UPDATE players p JOIN player_columns c ON c.player_id = p.id AND c.column_index = 0
+SET p.used_booking_types = c.used_booking_types,
+    p.upper_score = c.upper_score,
+    p.bonus = c.bonus,
+    p.lower_score = c.lower_score,
+    p.scored_kniffel = c.scored_kniffel,
+    p.kniffel_bonus_count = c.kniffel_bonus_count;
+
+DROP TABLE player_columns;
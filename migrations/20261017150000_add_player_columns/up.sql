@@ -0,0 +1,25 @@
+CREATE TABLE player_columns (
+    id INTEGER AUTO_INCREMENT PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    column_index INTEGER NOT NULL,
+    upper_score INTEGER NOT NULL DEFAULT 0,
+    bonus INTEGER NOT NULL DEFAULT 0,
+    lower_score INTEGER NOT NULL DEFAULT 0,
+    scored_kniffel BOOLEAN NOT NULL DEFAULT FALSE,
+    kniffel_bonus_count INTEGER NOT NULL DEFAULT 0,
+    used_booking_types VARCHAR(255) NOT NULL DEFAULT ''
+);
+
+INSERT INTO player_columns (player_id, column_index, upper_score, bonus, lower_score, scored_kniffel,
+                            kniffel_bonus_count, used_booking_types)
+SELECT id, 0, upper_score, bonus, lower_score, scored_kniffel, kniffel_bonus_count, used_booking_types
+FROM players;
+
+ALTER TABLE players DROP COLUMN used_booking_types;
+ALTER TABLE players DROP COLUMN upper_score;
+ALTER TABLE players DROP COLUMN bonus;
+ALTER TABLE players DROP COLUMN lower_score;
+ALTER TABLE players DROP COLUMN scored_kniffel;
+ALTER TABLE players DROP COLUMN kniffel_bonus_count;
+
+ALTER TABLE games ADD COLUMN column_count INTEGER NOT NULL DEFAULT 1;
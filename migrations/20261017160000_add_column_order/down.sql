@@ -0,0 +1,2 @@
+ALTER TABLE games DROP COLUMN announcement;
+ALTER TABLE player_columns DROP COLUMN column_order;
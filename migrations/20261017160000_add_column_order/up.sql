@@ -0,0 +1,2 @@
+ALTER TABLE player_columns ADD COLUMN column_order VARCHAR(255) NOT NULL DEFAULT 'FREE';
+ALTER TABLE games ADD COLUMN announcement VARCHAR(255) NOT NULL DEFAULT '';
@@ -0,0 +1 @@
+ALTER TABLE players ADD COLUMN hint_count INTEGER NOT NULL DEFAULT 0;
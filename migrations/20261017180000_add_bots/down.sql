@@ -0,0 +1,3 @@
+DROP TABLE bot_turns;
+
+ALTER TABLE players DROP COLUMN bot;
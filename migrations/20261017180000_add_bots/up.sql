@@ -0,0 +1,12 @@
+ALTER TABLE players ADD COLUMN bot VARCHAR(255) NOT NULL DEFAULT '';
+
+CREATE TABLE bot_turns (
+    id INTEGER AUTO_INCREMENT PRIMARY KEY,
+    game_id INTEGER NOT NULL,
+    player_name VARCHAR(255) NOT NULL,
+    rolls TEXT NOT NULL,
+    kept_dice TEXT NOT NULL,
+    booking_type VARCHAR(255) NOT NULL,
+    column_index INTEGER NOT NULL,
+    points INTEGER NOT NULL
+);
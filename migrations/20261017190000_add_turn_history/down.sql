@@ -0,0 +1,7 @@
+ALTER TABLE games DROP COLUMN turn_kept_dice;
+ALTER TABLE games DROP COLUMN turn_rolls;
+
+DELETE FROM turns WHERE player_name NOT IN (
+    SELECT name FROM players WHERE players.game_id = turns.game_id AND players.bot <> ''
+);
+RENAME TABLE turns TO bot_turns;
@@ -0,0 +1,4 @@
+RENAME TABLE bot_turns TO turns;
+
+ALTER TABLE games ADD COLUMN turn_rolls TEXT NOT NULL;
+ALTER TABLE games ADD COLUMN turn_kept_dice TEXT NOT NULL;
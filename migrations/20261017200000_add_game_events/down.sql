@@ -0,0 +1,3 @@
+DROP TABLE game_events;
+
+ALTER TABLE games DROP COLUMN event_count;
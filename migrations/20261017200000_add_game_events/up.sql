@@ -0,0 +1,17 @@
+ALTER TABLE games ADD COLUMN event_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE game_events (
+    id INTEGER AUTO_INCREMENT PRIMARY KEY,
+    game_id INTEGER NOT NULL,
+    sequence_number INTEGER NOT NULL,
+    created_at BIGINT NOT NULL,
+    kind VARCHAR(255) NOT NULL,
+    player_name VARCHAR(255) NOT NULL,
+    kept_dice VARCHAR(255) NOT NULL DEFAULT '',
+    dice VARCHAR(255) NOT NULL DEFAULT '',
+    booking_type VARCHAR(255) NOT NULL DEFAULT '',
+    column_index INTEGER NOT NULL DEFAULT 0,
+    points INTEGER NOT NULL DEFAULT 0,
+    stage VARCHAR(255) NOT NULL DEFAULT '',
+    UNIQUE KEY game_events_sequence (game_id, sequence_number)
+);
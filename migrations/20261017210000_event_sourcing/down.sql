@@ -0,0 +1,18 @@
+ALTER TABLE game_events ADD COLUMN stage VARCHAR(255) NOT NULL DEFAULT '';
+
+-- the start of a game is kept in its rows and the hints in players.hint_count, so nothing is lost by dropping them
+DELETE FROM game_events WHERE kind IN ('GAME_CREATED', 'HINT_GIVEN');
+UPDATE game_events SET kind = 'ROLLED' WHERE kind = 'DICE_ROLLED';
+UPDATE game_events SET kind = 'BOOKED' WHERE kind = 'CATEGORY_BOOKED';
+UPDATE game_events SET kind = 'PHASE_CHANGED', stage = 'Roll' WHERE kind = 'TURN_PASSED';
+UPDATE game_events SET kind = 'PHASE_CHANGED', stage = 'Ended' WHERE kind = 'GAME_ENDED';
+
+-- number the remaining events from 0 again, through negative numbers to keep the sequence numbers unique on the way
+UPDATE game_events e
+    JOIN (SELECT id, ROW_NUMBER() OVER (PARTITION BY game_id ORDER BY sequence_number) AS position FROM game_events) s
+    ON e.id = s.id
+SET e.sequence_number = -s.position;
+UPDATE game_events SET sequence_number = -sequence_number - 1 WHERE sequence_number < 0;
+UPDATE games g SET event_count = (SELECT COUNT(*) FROM game_events e WHERE e.game_id = g.id);
+
+ALTER TABLE game_events DROP COLUMN details;
@@ -0,0 +1,50 @@
+ALTER TABLE game_events ADD COLUMN details VARCHAR(4096) NOT NULL DEFAULT '';
+
+UPDATE game_events SET kind = 'DICE_ROLLED' WHERE kind = 'ROLLED';
+UPDATE game_events SET kind = 'CATEGORY_BOOKED' WHERE kind = 'BOOKED';
+UPDATE game_events SET kind = 'TURN_PASSED' WHERE kind = 'PHASE_CHANGED' AND stage = 'Roll';
+UPDATE game_events SET kind = 'GAME_ENDED', player_name = '' WHERE kind = 'PHASE_CHANGED' AND stage = 'Ended';
+DELETE FROM game_events WHERE kind = 'PHASE_CHANGED';
+
+ALTER TABLE game_events DROP COLUMN stage;
+
+-- the logs written so far start with the first throw of a game, a game can only be folded from a GAME_CREATED
+-- event in front of it. Games which were already running when the log was introduced miss bookings in their log
+-- and cannot be folded anyway, they are left as they are.
+CREATE TEMPORARY TABLE logged_games AS
+SELECT g.id, g.rule_set, g.dice_seed, MIN(e.created_at) AS created_at
+FROM games g
+    JOIN game_events e ON e.game_id = g.id
+GROUP BY g.id, g.rule_set, g.dice_seed
+HAVING MIN(e.sequence_number) = 0
+    AND SUM(e.kind = 'CATEGORY_BOOKED') = (
+        SELECT COALESCE(SUM(CASE WHEN c.used_booking_types = '' THEN 0
+            ELSE LENGTH(c.used_booking_types) - LENGTH(REPLACE(c.used_booking_types, ',', '')) + 1 END), 0)
+        FROM players p
+            JOIN player_columns c ON c.player_id = p.id
+        WHERE p.game_id = g.id);
+
+-- move the logs one step back, through negative numbers to keep the sequence numbers unique on the way
+UPDATE game_events SET sequence_number = -1 - sequence_number WHERE game_id IN (SELECT id FROM logged_games);
+UPDATE game_events SET sequence_number = -sequence_number WHERE sequence_number < 0;
+UPDATE games SET event_count = event_count + 1 WHERE id IN (SELECT id FROM logged_games);
+
+INSERT INTO game_events (game_id, sequence_number, created_at, kind, player_name, details)
+SELECT l.id, 0, l.created_at, 'GAME_CREATED', '',
+    CONCAT(
+        '{"players":[',
+        (SELECT GROUP_CONCAT(
+                CONCAT('{"name":', JSON_QUOTE(p.name), ',"bot":', COALESCE(JSON_QUOTE(NULLIF(p.bot, '')), 'null'), '}')
+                ORDER BY p.seat SEPARATOR ',')
+            FROM players p
+            WHERE p.game_id = l.id),
+        '],"ruleSet":', JSON_QUOTE(l.rule_set),
+        ',"columns":[',
+        (SELECT GROUP_CONCAT(JSON_QUOTE(c.column_order) ORDER BY c.column_index SEPARATOR ',')
+            FROM players p
+                JOIN player_columns c ON c.player_id = p.id
+            WHERE p.game_id = l.id AND p.seat = 0),
+        '],"diceSeed":', CAST(l.dice_seed AS UNSIGNED), '}')
+FROM logged_games l;
+
+DROP TEMPORARY TABLE logged_games;
@@ -0,0 +1,15 @@
+-- the events taken back by an undo are dropped with the undo, as the log did not know undos before
+DELETE e FROM game_events e
+    JOIN game_events u ON u.game_id = e.game_id AND u.kind = 'ACTION_UNDONE'
+        AND e.sequence_number >= u.undone_sequence AND e.sequence_number < u.sequence_number
+WHERE e.kind IN ('DICE_ROLLED', 'ANNOUNCED', 'CATEGORY_BOOKED', 'TURN_PASSED', 'GAME_ENDED');
+DELETE FROM game_events WHERE kind IN ('UNDO_REQUESTED', 'UNDO_APPROVED', 'ACTION_UNDONE');
+
+ALTER TABLE game_events DROP COLUMN undone_sequence;
+
+ALTER TABLE players DROP COLUMN undo_count;
+
+ALTER TABLE games DROP COLUMN undo_approved_by;
+ALTER TABLE games DROP COLUMN undo_requested_by;
+ALTER TABLE games DROP COLUMN undo_limit;
+ALTER TABLE games DROP COLUMN undo_policy;
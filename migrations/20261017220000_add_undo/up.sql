@@ -0,0 +1,8 @@
+ALTER TABLE games ADD COLUMN undo_policy VARCHAR(255) NOT NULL DEFAULT 'CONSENT';
+ALTER TABLE games ADD COLUMN undo_limit INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE games ADD COLUMN undo_requested_by VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE games ADD COLUMN undo_approved_by VARCHAR(1024) NOT NULL DEFAULT '';
+
+ALTER TABLE players ADD COLUMN undo_count INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE game_events ADD COLUMN undone_sequence INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+ALTER TABLE players DROP COLUMN token;
+
+ALTER TABLE games DROP COLUMN spectator_token;
@@ -0,0 +1,3 @@
+ALTER TABLE games ADD COLUMN spectator_token VARCHAR(255) NOT NULL DEFAULT '';
+
+ALTER TABLE players ADD COLUMN token VARCHAR(255) NOT NULL DEFAULT '';
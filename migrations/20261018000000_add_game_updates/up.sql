@@ -0,0 +1,7 @@
+CREATE TABLE game_updates (
+    id INTEGER AUTO_INCREMENT PRIMARY KEY,
+    game_id VARCHAR(255) NOT NULL,
+    message TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    KEY game_updates_created_at (created_at)
+);
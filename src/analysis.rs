@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use crate::game_error::GameError;
+use crate::game_logic::{ColumnOrder, GameOptions, GameState, KniffelGame, KniffelPlayer, ScriptedDice, Turn};
+use crate::solver::{ready_solver_for, supports, Decision, Solver};
+
+/// Expected final points a decision must give away to count as a blunder.
+pub const BLUNDER_THRESHOLD: f64 = 5.0;
+
+/// Expected losses up to this much count as the best decision, the solver's values are not more precise.
+const BEST_DECISION_TOLERANCE: f64 = 0.01;
+
+/// A keep or book decision of a finished game compared with the best one.
+#[derive(Debug, Clone)]
+pub struct DecisionReview {
+    pub player_name: String,
+    /// turn of the player, starting at 1
+    pub turn: usize,
+    /// throw of the turn after which the decision was taken, starting at 1
+    pub throw: usize,
+    pub dice: Vec<i32>,
+    pub decision: Decision,
+    pub best_decision: Decision,
+    /// final score the decision was expected to lead to with optimal play afterwards
+    pub expected_score: f64,
+    /// final score the best decision was expected to lead to
+    pub best_expected_score: f64,
+}
+
+impl DecisionReview {
+    /// Expected final points the decision gave away compared with the best one.
+    pub fn expected_loss(&self) -> f64 {
+        (self.best_expected_score - self.expected_score).max(0.0)
+    }
+
+    pub fn is_best(&self) -> bool {
+        self.expected_loss() <= BEST_DECISION_TOLERANCE
+    }
+
+    pub fn is_blunder(&self) -> bool {
+        self.expected_loss() >= BLUNDER_THRESHOLD
+    }
+}
+
+/// Summary of the decisions of one player.
+#[derive(Debug, Clone)]
+pub struct PlayerReview {
+    pub player_name: String,
+    pub decisions: usize,
+    pub best_decisions: usize,
+    pub blunders: usize,
+    /// expected final points given away by all decisions
+    pub expected_loss: f64,
+    /// share of best decisions in percent
+    pub accuracy: f64,
+}
+
+/// Review of every decision of a finished game.
+#[derive(Debug, Clone)]
+pub struct GameAnalysis {
+    /// one review per player, in seat order
+    pub players: Vec<PlayerReview>,
+    /// every decision in the order it was taken
+    pub decisions: Vec<DecisionReview>,
+}
+
+impl GameAnalysis {
+    /// Up to `count` blunders, the biggest first.
+    pub fn biggest_blunders(&self, count: usize) -> Vec<&DecisionReview> {
+        let mut blunders: Vec<&DecisionReview> = self.decisions.iter().filter(|review| review.is_blunder()).collect();
+        blunders.sort_by(|a, b| b.expected_loss().total_cmp(&a.expected_loss()));
+        blunders.truncate(count);
+        blunders
+    }
+}
+
+/// Replays the finished `game` from its `turns` and compares every decision with the best one of the solver.
+///
+/// Only possible for rule sets the solver supports and games with a single free column, since the solver
+/// maximizes the final score of one scorecard, and only once the solver's value table is ready. Fails if the
+/// turns do not lead to the game, e.g. because the game was started before turns were recorded for human players.
+pub fn analyze(game: &KniffelGame, turns: &[Turn]) -> Result<GameAnalysis, GameError> {
+    if !supports(game.rule_set) {
+        return Err(GameError::AnalysisNotAvailable(format!("rule set {} is not supported", game.rule_set.name())));
+    }
+    let solver = ready_solver_for(game.rule_set)
+        .ok_or_else(|| GameError::SolverNotReady(game.rule_set.name().to_string()))?;
+    if game.state != GameState::Ended {
+        return Err(GameError::AnalysisNotAvailable("the game has not ended".to_string()));
+    }
+    let seated_players = game.seated_players();
+    if seated_players.iter().any(|player| player.columns.len() != 1 || player.columns[0].order != ColumnOrder::Free) {
+        return Err(GameError::AnalysisNotAvailable("only for a single free column".to_string()));
+    }
+
+    // bots are replayed as humans, their recorded decisions are taken instead of playing them again
+    let players = seated_players.iter().map(|player| KniffelPlayer::new(&player.name)).collect();
+    let options = GameOptions { rule_set: game.rule_set, columns: vec![ColumnOrder::Free], ..GameOptions::default() };
+    let mut replay = KniffelGame::new(players, options, Box::new(ScriptedDice::new(thrown_dice(turns)?)))?;
+
+    let mut decisions = Vec::new();
+    let mut turn_numbers: HashMap<&str, usize> = HashMap::new();
+    for turn in turns {
+        if replay.current_player != turn.player_name || turn.rolls.len() != turn.kept_dice.len() + 1 {
+            return Err(history_mismatch());
+        }
+        let turn_number = turn_numbers.entry(&turn.player_name).or_insert(0);
+        *turn_number += 1;
+
+        for (throw, dice_to_keep) in turn.kept_dice.iter().enumerate() {
+            check_dice(&replay, &turn.rolls[throw])?;
+            decisions.push(review(&solver, &replay, *turn_number, throw + 1, Decision::Keep(dice_to_keep.clone()))?);
+            replay.re_roll_dice(dice_to_keep).map_err(|_| history_mismatch())?;
+        }
+        check_dice(&replay, &turn.rolls[turn.kept_dice.len()])?;
+        decisions.push(review(&solver, &replay, *turn_number, turn.rolls.len(), Decision::Book(turn.booking_type))?);
+        replay.book_dice_roll(turn.booking_type, Some(turn.column)).map_err(|_| history_mismatch())?;
+    }
+    if replay.state != GameState::Ended {
+        return Err(history_mismatch());
+    }
+
+    let players = seated_players.iter()
+        .map(|player| {
+            let reviews: Vec<&DecisionReview> = decisions.iter()
+                .filter(|review| review.player_name == player.name)
+                .collect();
+            let best_decisions = reviews.iter().filter(|review| review.is_best()).count();
+            PlayerReview {
+                player_name: player.name.to_string(),
+                decisions: reviews.len(),
+                best_decisions,
+                blunders: reviews.iter().filter(|review| review.is_blunder()).count(),
+                expected_loss: reviews.iter().map(|review| review.expected_loss()).sum(),
+                accuracy: if reviews.is_empty() { 100.0 } else { 100.0 * best_decisions as f64 / reviews.len() as f64 },
+            }
+        })
+        .collect();
+    Ok(GameAnalysis { players, decisions })
+}
+
+fn history_mismatch() -> GameError {
+    GameError::AnalysisNotAvailable("the recorded turns do not match the game".to_string())
+}
+
+/// All dice thrown in `turns` in the order they were thrown.
+fn thrown_dice(turns: &[Turn]) -> Result<Vec<i32>, GameError> {
+    let mut thrown = Vec::new();
+    for turn in turns {
+        thrown.extend(turn.rolls.first().ok_or_else(history_mismatch)?);
+        for (dice_to_keep, dice) in turn.kept_dice.iter().zip(turn.rolls.iter().skip(1)) {
+            let mut new_dice = dice.clone();
+            for kept in dice_to_keep {
+                let position = new_dice.iter().position(|value| value == kept).ok_or_else(history_mismatch)?;
+                new_dice.remove(position);
+            }
+            thrown.extend(new_dice);
+        }
+    }
+    if thrown.is_empty() {
+        return Err(history_mismatch());
+    }
+    Ok(thrown)
+}
+
+/// Fails unless the dice on the table of `replay` are `dice` in any order.
+fn check_dice(replay: &KniffelGame, dice: &[i32]) -> Result<(), GameError> {
+    let mut on_table = replay.dice_rolls.clone();
+    on_table.sort();
+    let mut expected = dice.to_vec();
+    expected.sort();
+    if on_table == expected { Ok(()) } else { Err(history_mismatch()) }
+}
+
+fn review(solver: &Solver, replay: &KniffelGame, turn: usize, throw: usize, decision: Decision) -> Result<DecisionReview, GameError> {
+    let position = solver.position(replay).ok_or_else(history_mismatch)?;
+    let best = solver.alternatives(&position).into_iter().next().ok_or_else(history_mismatch)?;
+    let expected_score = solver.decision_value(&position, &decision).ok_or_else(history_mismatch)?;
+    Ok(DecisionReview {
+        player_name: replay.current_player.to_string(),
+        turn,
+        throw,
+        dice: replay.dice_rolls.clone(),
+        decision,
+        best_decision: best.decision,
+        expected_score,
+        best_expected_score: best.expected_score.max(expected_score),
+    })
+}
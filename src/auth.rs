@@ -0,0 +1,102 @@
+use kniffel::game_error::GameError;
+use kniffel::game_logic::KniffelGame;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+/// Header carrying the seat token of a player or the spectator token of a game.
+pub const TOKEN_HEADER: &str = "X-Game-Token";
+
+/// Secret tokens of a game, handed out once when the game is created. Games created before tokens were
+/// introduced have none and stay open to everyone.
+#[derive(Debug, Clone, Default)]
+pub struct GameTokens {
+    /// seat token of every human player, by name
+    pub seats: HashMap<String, String>,
+    /// token to watch the game without playing
+    pub spectator: String,
+}
+
+impl GameTokens {
+    /// New random tokens for the human players of `game` and for spectators.
+    pub fn issue(game: &KniffelGame) -> Self {
+        GameTokens {
+            seats: game.players.values()
+                .filter(|player| player.bot.is_none())
+                .map(|player| (player.name.to_string(), new_token()))
+                .collect(),
+            spectator: new_token(),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.spectator.is_empty()
+    }
+
+    /// Fails unless `token` is the seat token of `player_name`.
+    pub fn authorize_player(&self, token: &GameToken, player_name: &str) -> Result<(), GameError> {
+        if self.is_open() {
+            return Ok(());
+        }
+        let token = token.value()?;
+        match self.seats.get(player_name) {
+            Some(seat_token) if tokens_match(seat_token, token) => Ok(()),
+            _ => Err(GameError::TokenRejected(format!("not the seat token of {}", player_name))),
+        }
+    }
+
+    /// Fails unless `token` is the seat token of any player of the game.
+    pub fn authorize_any_player(&self, token: &GameToken) -> Result<(), GameError> {
+        if self.is_open() {
+            return Ok(());
+        }
+        let token = token.value()?;
+        if self.seats.values().any(|seat_token| tokens_match(seat_token, token)) {
+            Ok(())
+        } else {
+            Err(GameError::TokenRejected("not the seat token of a player of this game".to_string()))
+        }
+    }
+
+    /// Fails unless `token` is the seat token of any player or the spectator token of the game.
+    pub fn authorize_reader(&self, token: &GameToken) -> Result<(), GameError> {
+        if self.is_open() || token.value().is_ok_and(|token| tokens_match(&self.spectator, token)) {
+            return Ok(());
+        }
+        self.authorize_any_player(token)
+    }
+}
+
+/// The token sent with a request in the `X-Game-Token` header, if any.
+pub struct GameToken(pub Option<String>);
+
+impl GameToken {
+    fn value(&self) -> Result<&str, GameError> {
+        self.0.as_deref().ok_or(GameError::TokenMissing)
+    }
+
+    /// The token of the header, or `token` if the header is missing, e.g. for browsers' event sources
+    /// that cannot send headers.
+    pub fn or(self, token: Option<String>) -> Self {
+        GameToken(self.0.or(token))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for GameToken {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(GameToken(request.headers().get_one(TOKEN_HEADER).map(str::to_string)))
+    }
+}
+
+fn new_token() -> String {
+    uuid::Uuid::new_v4().to_string().replace("-", "")
+}
+
+/// Compares the tokens in constant time, so the time taken does not tell how much of a guess was right.
+fn tokens_match(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected.bytes().zip(actual.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
@@ -0,0 +1,246 @@
+//! Plays games between bot strategies without a database and reports how they score.
+//!
+//! ```bash
+//! cargo run --release --bin simulate -- --games 1000 --strategies HEURISTIC,OPTIMAL --rule-set KNIFFEL --format table
+//! ```
+use std::collections::BTreeMap;
+use std::env;
+use std::process;
+use std::str::FromStr;
+use serde::Serialize;
+use kniffel::bot::BotStrategy;
+use kniffel::game_error::GameError;
+use kniffel::game_logic::{GameOptions, GameState, KniffelGame, KniffelPlayer, SeededDice, Turn};
+use kniffel::rule_set::{rule_set_by_name, RuleSet, KNIFFEL};
+use kniffel::solver::solver_for;
+
+const USAGE: &str = "usage: simulate [--games N] [--strategies S1,S2,..] [--rule-set NAME] [--seed N] [--format table|json]
+  strategies: RANDOM, GREEDY, HEURISTIC, OPTIMAL (default HEURISTIC), one seat each
+  rule sets:  KNIFFEL (default), YAHTZEE, YATZY, GENERALA, MAXI_YATZY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug)]
+struct Config {
+    games: usize,
+    strategies: Vec<BotStrategy>,
+    rule_set: &'static dyn RuleSet,
+    seed: u64,
+    format: OutputFormat,
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config {
+            games: 1000,
+            strategies: vec![BotStrategy::Heuristic],
+            rule_set: &KNIFFEL,
+            seed: 0,
+            format: OutputFormat::Table,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--games" => config.games = value()?.parse().map_err(|_| "--games needs a number".to_string())?,
+                "--strategies" => config.strategies = value()?
+                    .split(',')
+                    .map(|name| BotStrategy::from_str(name).map_err(|_| format!("unknown strategy {}", name)))
+                    .collect::<Result<_, _>>()?,
+                "--rule-set" => {
+                    let name = value()?;
+                    config.rule_set = rule_set_by_name(&name).ok_or_else(|| format!("unknown rule set {}", name))?;
+                }
+                "--seed" => config.seed = value()?.parse().map_err(|_| "--seed needs a number".to_string())?,
+                "--format" => config.format = match value()?.as_str() {
+                    "table" => OutputFormat::Table,
+                    "json" => OutputFormat::Json,
+                    format => return Err(format!("unknown format {}", format)),
+                },
+                "--help" | "-h" => return Err(USAGE.to_string()),
+                _ => return Err(format!("unknown argument {}\n{}", arg, USAGE)),
+            }
+        }
+        if config.strategies.is_empty() || config.games == 0 {
+            return Err(USAGE.to_string());
+        }
+        Ok(config)
+    }
+}
+
+/// Points a seat booked into one category over all games.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CategoryStats {
+    /// points per game, including the bonuses the booking earned
+    mean_points: f64,
+    /// share of games in which the category was crossed out with 0 points
+    zero_rate: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SeatStats {
+    seat: usize,
+    strategy: String,
+    mean_score: f64,
+    std_dev: f64,
+    min_score: i32,
+    max_score: i32,
+    /// share of games in which the upper-section bonus was reached
+    bonus_rate: f64,
+    /// share of games won, ties are split between the winners
+    win_rate: f64,
+    categories: BTreeMap<String, CategoryStats>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    rule_set: String,
+    games: usize,
+    seed: u64,
+    seats: Vec<SeatStats>,
+}
+
+/// Results of one seat, collected game by game.
+#[derive(Debug, Default)]
+struct SeatResults {
+    scores: Vec<i32>,
+    bonus_games: usize,
+    wins: f64,
+    /// points and number of zero bookings per category
+    categories: BTreeMap<String, (i64, usize)>,
+}
+
+fn seat_name(seat: usize, strategy: BotStrategy) -> String {
+    format!("{}-{}", seat + 1, strategy)
+}
+
+fn play_game(config: &Config, game_number: usize) -> Result<KniffelGame, GameError> {
+    let mut players = config.strategies.iter()
+        .enumerate()
+        .map(|(seat, &strategy)| KniffelPlayer::bot(&seat_name(seat, strategy), strategy))
+        .collect::<Vec<_>>();
+    // rotate the seats so that no strategy always starts
+    players.rotate_left(game_number % config.strategies.len());
+    let options = GameOptions { rule_set: config.rule_set, ..GameOptions::default() };
+    let dice = SeededDice::new(config.seed.wrapping_add(game_number as u64));
+    KniffelGame::new(players, options, Box::new(dice))
+}
+
+fn record_game(game: &KniffelGame, config: &Config, results: &mut [SeatResults]) {
+    let names: Vec<String> = config.strategies.iter()
+        .enumerate()
+        .map(|(seat, &strategy)| seat_name(seat, strategy))
+        .collect();
+    let scores: Vec<i32> = names.iter().map(|name| game.players[name].score).collect();
+    let best = scores.iter().copied().max().unwrap_or(0);
+    let winners = scores.iter().filter(|&&score| score == best).count() as f64;
+
+    for (seat, name) in names.iter().enumerate() {
+        let player = &game.players[name];
+        let seat_results = &mut results[seat];
+        seat_results.scores.push(player.score);
+        if player.columns.iter().any(|column| column.bonus > 0) {
+            seat_results.bonus_games += 1;
+        }
+        if player.score == best {
+            seat_results.wins += 1.0 / winners;
+        }
+        for Turn { booking_type, points, .. } in game.turns.iter().filter(|turn| &turn.player_name == name) {
+            let entry = seat_results.categories.entry(booking_type.to_string()).or_default();
+            entry.0 += *points as i64;
+            if *points == 0 {
+                entry.1 += 1;
+            }
+        }
+    }
+}
+
+fn report(config: &Config, results: &[SeatResults]) -> Report {
+    let games = config.games as f64;
+    let seats = results.iter()
+        .enumerate()
+        .map(|(seat, seat_results)| {
+            let mean = seat_results.scores.iter().map(|&score| score as f64).sum::<f64>() / games;
+            let variance = seat_results.scores.iter().map(|&score| (score as f64 - mean).powi(2)).sum::<f64>() / games;
+            SeatStats {
+                seat: seat + 1,
+                strategy: config.strategies[seat].to_string(),
+                mean_score: mean,
+                std_dev: variance.sqrt(),
+                min_score: seat_results.scores.iter().copied().min().unwrap_or(0),
+                max_score: seat_results.scores.iter().copied().max().unwrap_or(0),
+                bonus_rate: seat_results.bonus_games as f64 / games,
+                win_rate: seat_results.wins / games,
+                categories: seat_results.categories.iter()
+                    .map(|(category, &(points, zeros))| (category.clone(), CategoryStats {
+                        mean_points: points as f64 / games,
+                        zero_rate: zeros as f64 / games,
+                    }))
+                    .collect(),
+            }
+        })
+        .collect();
+    Report { rule_set: config.rule_set.name().to_string(), games: config.games, seed: config.seed, seats }
+}
+
+fn print_table(config: &Config, report: &Report) {
+    println!("{} games of {}, seed {}", report.games, report.rule_set, report.seed);
+    println!();
+    println!("{:<6}{:<12}{:>9}{:>9}{:>7}{:>7}{:>9}{:>9}", "seat", "strategy", "mean", "stddev", "min", "max", "bonus", "wins");
+    for seat in &report.seats {
+        println!("{:<6}{:<12}{:>9.2}{:>9.2}{:>7}{:>7}{:>8.1}%{:>8.1}%", seat.seat, seat.strategy, seat.mean_score,
+                 seat.std_dev, seat.min_score, seat.max_score, seat.bonus_rate * 100.0, seat.win_rate * 100.0);
+    }
+    println!();
+    print!("{:<18}", "category");
+    for seat in &report.seats {
+        print!("{:>26}", format!("{}-{} mean / zero", seat.seat, seat.strategy));
+    }
+    println!();
+    for category in config.rule_set.categories() {
+        print!("{:<18}", category.to_string());
+        for seat in &report.seats {
+            let stats = seat.categories.get(&category.to_string());
+            let (mean, zero_rate) = stats.map_or((0.0, 0.0), |stats| (stats.mean_points, stats.zero_rate));
+            print!("{:>26}", format!("{:.2} / {:.1}%", mean, zero_rate * 100.0));
+        }
+        println!();
+    }
+}
+
+fn main() {
+    let config = Config::from_args(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(2);
+    });
+
+    // OPTIMAL bots play heuristic until the value table is ready, so wait for it
+    if config.strategies.contains(&BotStrategy::Optimal) {
+        solver_for(config.rule_set);
+    }
+
+    let mut results: Vec<SeatResults> = config.strategies.iter().map(|_| SeatResults::default()).collect();
+    for game_number in 0..config.games {
+        let game = play_game(&config, game_number).unwrap_or_else(|error| {
+            eprintln!("game {} failed: {}", game_number, error);
+            process::exit(1);
+        });
+        if game.state != GameState::Ended {
+            eprintln!("game {} did not end, a bot got stuck", game_number);
+            process::exit(1);
+        }
+        record_game(&game, &config, &mut results);
+    }
+
+    let report = report(&config, &results);
+    match config.format {
+        OutputFormat::Table => print_table(&config, &report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report).expect("report is serializable")),
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::str::FromStr;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::game_logic::{BookingType, KniffelGame};
+use crate::scoring::{count_values, is_small_straight};
+use crate::solver::{ready_solver_for, Decision};
+
+/// How a computer-controlled player decides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BotStrategy {
+    /// Re-rolls random dice or books a random category
+    Random,
+
+    /// Books the category with the best immediate score right after the first throw
+    Greedy,
+
+    /// Rules of thumb: goes for pairs and straights, books big hits right away
+    Heuristic,
+
+    /// Maximizes the expected final score with the solver, plays heuristic where the solver has no answer
+    /// or its value table is not ready yet
+    Optimal,
+}
+
+impl fmt::Display for BotStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BotStrategy::Random => write!(f, "RANDOM"),
+            BotStrategy::Greedy => write!(f, "GREEDY"),
+            BotStrategy::Heuristic => write!(f, "HEURISTIC"),
+            BotStrategy::Optimal => write!(f, "OPTIMAL"),
+        }
+    }
+}
+
+impl FromStr for BotStrategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "RANDOM" => Ok(BotStrategy::Random),
+            "GREEDY" => Ok(BotStrategy::Greedy),
+            "HEURISTIC" => Ok(BotStrategy::Heuristic),
+            "OPTIMAL" => Ok(BotStrategy::Optimal),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Categories a heuristic bot crosses out first when nothing scores.
+const SACRIFICE_ORDER: [BookingType; 20] = [
+    BookingType::Ones,
+    BookingType::Kniffel,
+    BookingType::Twos,
+    BookingType::FiveOfAKind,
+    BookingType::FullStraight,
+    BookingType::Tower,
+    BookingType::Castle,
+    BookingType::ThreePairs,
+    BookingType::FourOfAKind,
+    BookingType::LargeStraight,
+    BookingType::Threes,
+    BookingType::SmallStraight,
+    BookingType::FullHouse,
+    BookingType::TwoPairs,
+    BookingType::Fours,
+    BookingType::ThreeOfAKind,
+    BookingType::OnePair,
+    BookingType::Fives,
+    BookingType::Sixes,
+    BookingType::Chance,
+];
+
+/// Immediate points of at least this much are booked by a heuristic bot without re-rolling.
+const HEURISTIC_BOOKING_THRESHOLD: i32 = 25;
+
+impl BotStrategy {
+    /// The next decision of the current player of `game`, None if they cannot act.
+    pub fn decide(&self, game: &KniffelGame) -> Option<Decision> {
+        match self {
+            BotStrategy::Random => decide_random(game),
+            BotStrategy::Greedy => best_booking(game).map(|(booking_type, _)| Decision::Book(booking_type)),
+            BotStrategy::Heuristic => decide_heuristic(game),
+            BotStrategy::Optimal => ready_solver_for(game.rule_set)
+                .and_then(|solver| solver.position(game).and_then(|position| solver.best_decision(&position)))
+                .or_else(|| decide_heuristic(game)),
+        }
+    }
+}
+
+/// The booking type with the highest immediate points for the current player and these points.
+fn best_booking(game: &KniffelGame) -> Option<(BookingType, i32)> {
+    game.booking_options()
+        .into_iter()
+        .map(|(booking_type, _, points)| (booking_type, points))
+        .max_by_key(|&(_, points)| points)
+}
+
+fn decide_random(game: &KniffelGame) -> Option<Decision> {
+    let mut rng = rand::thread_rng();
+    if game.can_roll() && rng.gen_bool(0.5) {
+        let dice_to_keep = game.dice_rolls.iter()
+            .copied()
+            .filter(|_| rng.gen_bool(0.5))
+            .collect();
+        return Some(Decision::Keep(dice_to_keep));
+    }
+    game.booking_options()
+        .choose(&mut rng)
+        .map(|&(booking_type, _, _)| Decision::Book(booking_type))
+}
+
+fn decide_heuristic(game: &KniffelGame) -> Option<Decision> {
+    let options = game.booking_options();
+    let (best_type, best_points) = best_booking(game)?;
+    let can_book = |booking_type: &BookingType| options.iter().any(|(bt, _, _)| bt == booking_type);
+
+    if game.can_roll() && best_points < HEURISTIC_BOOKING_THRESHOLD {
+        if is_small_straight(&game.dice_rolls) && can_book(&BookingType::LargeStraight) {
+            return Some(Decision::Keep(straight_draw(&game.dice_rolls)));
+        }
+        let counts = count_values(&game.dice_rolls);
+        // most frequent value, the higher one on ties
+        let value = (1..=6).max_by_key(|&value| (counts[(value - 1) as usize], value)).unwrap_or(6);
+        return Some(Decision::Keep(vec![value; counts[(value - 1) as usize] as usize]));
+    }
+
+    if best_points > 0 {
+        return Some(Decision::Book(best_type));
+    }
+    SACRIFICE_ORDER.iter()
+        .find(|booking_type| can_book(booking_type))
+        .or_else(|| options.first().map(|(booking_type, _, _)| booking_type))
+        .map(|&booking_type| Decision::Book(booking_type))
+}
+
+/// The four dice of a small straight on the table.
+fn straight_draw(dice_rolls: &[i32]) -> Vec<i32> {
+    [[3, 4, 5, 6], [2, 3, 4, 5], [1, 2, 3, 4]].iter()
+        .find(|straight| straight.iter().all(|value| dice_rolls.contains(value)))
+        .map(|straight| straight.to_vec())
+        .unwrap_or_default()
+}
@@ -1,14 +1,23 @@
-use crate::game_logic::KniffelGame;
-use crate::models::{Game, LastInsertId, Player};
+use kniffel::bot::BotStrategy;
+use kniffel::game_error::GameError;
+use kniffel::game_logic::{BookingType, ColumnOrder, GameEvent, GameEventKind, GameState, KniffelGame, KniffelPlayer, PendingUndo, ScoreColumn, SeatedPlayer, SeededDice, Turn, UndoPolicy};
+use rocket::serde::{Deserialize, Serialize};
+use kniffel::rule_set::rule_set_by_name;
+use crate::auth::GameTokens;
+use crate::models::{Game, GameEventRecord, GameUpdateRecord, LastInsertId, Player, PlayerColumn, TurnRecord};
 use diesel::prelude::*;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
+use diesel::r2d2::PooledConnection;
 use diesel::result::Error;
-use diesel::{insert_into, sql_query, update, Connection, MysqlConnection, QueryDsl, RunQueryDsl};
+use diesel::{delete, insert_into, sql_query, update, Connection, MysqlConnection, QueryDsl, RunQueryDsl};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenvy::dotenv;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::str::FromStr;
 use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use lazy_static::lazy_static;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
@@ -39,33 +48,40 @@ pub(crate) fn init() {
         .expect("Failed to run database migrations");
 }
 
-pub(crate) fn persist_new_game(kniffel_game: &KniffelGame) {
-    let connection = &mut POOL.lock().unwrap().get().unwrap();
+fn get_connection() -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, GameError> {
+    POOL.lock()
+        .map_err(GameError::storage)?
+        .get()
+        .map_err(GameError::storage)
+}
+
+pub(crate) fn persist_new_game(kniffel_game: &KniffelGame, tokens: &GameTokens) -> Result<(), GameError> {
+    let connection = &mut get_connection()?;
     connection.transaction::<_, Error, _>(|con| {
-        insert_game_to_db(con, &kniffel_game);
+        insert_game_to_db(con, kniffel_game, tokens)?;
 
-        let game_id = get_last_id(con);
+        let game_id = get_last_id(con)?;
 
-        insert_players_to_db(con, &kniffel_game, game_id);
+        insert_players_to_db(con, kniffel_game, tokens, game_id)?;
+        insert_turns_to_db(con, game_id, &kniffel_game.turns)?;
+        insert_events_to_db(con, game_id, &kniffel_game.events)?;
 
         Ok(())
-    }).expect("Failed to commit game");
+    }).map_err(GameError::storage)
 }
 
-fn get_last_id(con: &mut MysqlConnection) -> i32 {
-    let last_id_result = sql_query("SELECT LAST_INSERT_ID() as last_insert_id")
-        .load::<LastInsertId>(con);
+fn get_last_id(con: &mut MysqlConnection) -> Result<i32, Error> {
+    let vec_last_id = sql_query("SELECT LAST_INSERT_ID() as last_insert_id")
+        .load::<LastInsertId>(con)?;
 
-    let vec_last_id = last_id_result.expect("failed to get vec for last_insert_id");
+    let last_id = vec_last_id.first().ok_or(Error::NotFound)?;
 
-    let last_id = vec_last_id.get(0).expect("failed to get item for last_insert_id");
-
-    last_id.last_insert_id
+    Ok(last_id.last_insert_id)
 }
 
-fn insert_game_to_db(con: &mut MysqlConnection, kniffel_game: &KniffelGame) {
+fn insert_game_to_db(con: &mut MysqlConnection, kniffel_game: &KniffelGame, tokens: &GameTokens) -> Result<(), Error> {
     use crate::schema::games::dsl::*;
-    let _ = insert_into(games)
+    insert_into(games)
         .values((
             game_id.eq(kniffel_game.game_id.clone()),
             roll_round.eq(kniffel_game.roll_round),
@@ -75,81 +91,245 @@ fn insert_game_to_db(con: &mut MysqlConnection, kniffel_game: &KniffelGame) {
                 .map(|x| x.to_string())
                 .collect::<Vec<_>>()
                 .join(",")),
-            current_player.eq(kniffel_game.current_player.to_string())
+            current_player.eq(kniffel_game.current_player.to_string()),
+            rule_set.eq(kniffel_game.rule_set.name()),
+            dice_seed.eq(kniffel_game.dice.seed() as i64),
+            dice_position.eq(kniffel_game.dice.position() as i64),
+            column_count.eq(kniffel_game.column_count as i32),
+            announcement.eq(""),
+            turn_rolls.eq(join_dice_lists(&kniffel_game.turn_rolls)),
+            turn_kept_dice.eq(join_dice_lists(&kniffel_game.turn_kept_dice)),
+            event_count.eq(kniffel_game.event_count),
+            undo_policy.eq(kniffel_game.undo_policy.to_string()),
+            undo_limit.eq(kniffel_game.undo_limit),
+            spectator_token.eq(tokens.spectator.to_string())
         ))
-        .execute(con);
+        .execute(con)?;
+    Ok(())
 }
 
-fn insert_players_to_db(con: &mut MysqlConnection, kniffel_game: &KniffelGame, game_id_param: i32) {
+fn insert_players_to_db(con: &mut MysqlConnection, kniffel_game: &KniffelGame, tokens: &GameTokens,
+                        game_id_param: i32) -> Result<(), Error> {
     use crate::schema::players::dsl::*;
 
-    kniffel_game.players.values().for_each(|player_var| {
-        let _ = insert_into(players)
+    for player_var in kniffel_game.players.values() {
+        insert_into(players)
             .values((
                 game_id.eq(game_id_param),
                 name.eq(player_var.name.to_string()),
+                seat.eq(player_var.seat),
                 score.eq(0),
-                used_booking_types.eq("")
+                saved_rolls.eq(0),
+                hint_count.eq(0),
+                undo_count.eq(0),
+                bot.eq(player_var.bot.map(|strategy| strategy.to_string()).unwrap_or_default()),
+                token.eq(tokens.seats.get(&player_var.name).cloned().unwrap_or_default())
+            ))
+            .execute(con)?;
+
+        let player_id_param = get_last_id(con)?;
+        insert_player_columns_to_db(con, player_id_param, &player_var.columns)?;
+    }
+    Ok(())
+}
+
+fn insert_player_columns_to_db(con: &mut MysqlConnection, player_id_param: i32, columns: &[ScoreColumn]) -> Result<(), Error> {
+    use crate::schema::player_columns::dsl::*;
+
+    for (index, column) in columns.iter().enumerate() {
+        insert_into(player_columns)
+            .values((
+                player_id.eq(player_id_param),
+                column_index.eq(index as i32),
+                upper_score.eq(0),
+                bonus.eq(0),
+                lower_score.eq(0),
+                scored_kniffel.eq(false),
+                kniffel_bonus_count.eq(0),
+                used_booking_types.eq(""),
+                column_order.eq(column.order.to_string())
             ))
-            .execute(con);
-    });
+            .execute(con)?;
+    }
+    Ok(())
 }
 
-pub(crate) fn load_game_from_persistent_store(game_id_param: &String) -> Option<KniffelGame> {
-    let connection = &mut POOL.lock().unwrap().get().unwrap();
-    let option_game = load_game(connection, game_id_param);
+/// Loads a game from its rows, which stay the source of truth of the game. The event log is written next to them and
+/// only folded for the replay, undo and rebuild of a game.
+pub(crate) fn load_game_from_persistent_store(game_id_param: &String) -> Result<KniffelGame, GameError> {
+    let connection = &mut get_connection()?;
+    let option_game = load_game(connection, game_id_param).map_err(GameError::storage)?;
 
-    let game = option_game.expect("failed to load game");
+    let game = option_game.ok_or_else(|| GameError::UnknownGame(game_id_param.to_string()))?;
 
     let game_id_id = game.id;
 
-    let players = load_players(connection, game_id_id);
+    let players = load_players(connection, game_id_id).map_err(GameError::storage)?;
+    let player_ids: Vec<i32> = players.iter().map(|player| player.id).collect();
+    let player_columns = load_player_columns(connection, &player_ids).map_err(GameError::storage)?;
 
-    Some(KniffelGame::from_db(&game, players.as_slice()))
+    game_from_db(&game, players.as_slice(), player_columns.as_slice())
 }
 
-fn load_game(connection: &mut MysqlConnection, game_id_param: &String) -> Option<Game> {
+fn game_from_db(game: &Game, players: &[Player], player_columns: &[PlayerColumn]) -> Result<KniffelGame, GameError> {
+    let kniffel_players_map = players.iter()
+        .map(|player| Ok((player.name.clone(), player_from_db(player, player_columns)?)))
+        .collect::<Result<HashMap<_, _>, GameError>>()?;
+
+    let rule_set = rule_set_by_name(&game.rule_set)
+        .ok_or_else(|| GameError::Storage(format!("Unknown rule set {}", game.rule_set)))?;
+
+    let result: Vec<i32> = game.dice_rolls
+        .split(',')
+        .map(|s| s.parse::<i32>()) // Parse each segment as i32
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(GameError::storage)?;
+    if result.len() != rule_set.dice_count() {
+        return Err(GameError::Storage(format!("Expected a list of {} elements", rule_set.dice_count())));
+    }
+
+    let turn_rolls = split_dice_lists(&game.turn_rolls)?;
+
+    Ok(KniffelGame {
+        players: kniffel_players_map,
+        game_id: game.game_id.to_string(),
+        roll_round: game.roll_round,
+        current_player: game.current_player.to_string(),
+        state: GameState::from_str(&game.stage.to_string())
+            .map_err(|_| GameError::Storage(format!("Unknown stage {}", game.stage)))?,
+        dice_rolls: result,
+        rule_set,
+        column_count: game.column_count as usize,
+        announcement: BookingType::from_str(&game.announcement).ok(),
+        dice: Box::new(SeededDice::resume(game.dice_seed as u64, game.dice_position as u64)),
+        turns: Vec::new(),
+        turn_kept_dice: split_kept_dice(&game.turn_kept_dice, turn_rolls.len())?,
+        turn_rolls,
+        events: Vec::new(),
+        event_count: game.event_count,
+        undo_policy: UndoPolicy::from_str(&game.undo_policy)
+            .map_err(|_| GameError::Storage(format!("Unknown undo policy {}", game.undo_policy)))?,
+        undo_limit: game.undo_limit,
+        pending_undo: pending_undo_from_db(game),
+    })
+}
+
+fn pending_undo_from_db(game: &Game) -> Option<PendingUndo> {
+    if game.undo_requested_by.is_empty() {
+        return None;
+    }
+    Some(PendingUndo {
+        requested_by: game.undo_requested_by.to_string(),
+        approved_by: game.undo_approved_by.split(',')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+fn player_from_db(player: &Player, player_columns: &[PlayerColumn]) -> Result<KniffelPlayer, GameError> {
+    let columns = player_columns.iter()
+        .filter(|player_column| player_column.player_id == player.id)
+        .map(score_column_from_db)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(KniffelPlayer {
+        name: player.name.clone(),
+        seat: player.seat,
+        score: player.score,
+        saved_rolls: player.saved_rolls,
+        hint_count: player.hint_count,
+        undo_count: player.undo_count,
+        bot: BotStrategy::from_str(&player.bot).ok(),
+        columns,
+    })
+}
+
+fn score_column_from_db(player_column: &PlayerColumn) -> Result<ScoreColumn, GameError> {
+    let used_booking_types = player_column
+        .used_booking_types
+        .split(',')
+        .filter_map(|s| s.parse::<BookingType>().ok())
+        .collect::<HashSet<_>>();
+
+    Ok(ScoreColumn {
+        multiplier: player_column.column_index + 1,
+        order: ColumnOrder::from_str(&player_column.column_order)
+            .map_err(|_| GameError::Storage(format!("Unknown column order {}", player_column.column_order)))?,
+        upper_score: player_column.upper_score,
+        bonus: player_column.bonus,
+        lower_score: player_column.lower_score,
+        scored_kniffel: player_column.scored_kniffel,
+        kniffel_bonus_count: player_column.kniffel_bonus_count,
+        used_booking_types,
+    })
+}
+
+fn load_game(connection: &mut MysqlConnection, game_id_param: &String) -> Result<Option<Game>, Error> {
     use crate::schema::games::dsl::*;
 
     let result = games
         .select(Game::as_select())
         .limit(1)
         .filter(game_id.eq(game_id_param))
-        .load(connection)
-        .expect("failed to load game");
+        .load(connection)?;
 
-    result.into_iter().next()
+    Ok(result.into_iter().next())
 }
 
-fn load_players(connection: &mut MysqlConnection, game_id_param: i32) -> Vec<Player> {
+fn load_players(connection: &mut MysqlConnection, game_id_param: i32) -> Result<Vec<Player>, Error> {
     use crate::schema::players::dsl::*;
-    let result_players = players
+    players
         .select(Player::as_select())
         .filter(game_id.eq(game_id_param))
+        .order(seat.asc())
         .load(connection)
-        .expect("failed to load players");
+}
 
-    result_players
+fn load_player_columns(connection: &mut MysqlConnection, player_ids: &[i32]) -> Result<Vec<PlayerColumn>, Error> {
+    use crate::schema::player_columns::dsl::*;
+    player_columns
+        .select(PlayerColumn::as_select())
+        .filter(player_id.eq_any(player_ids))
+        .order(column_index.asc())
+        .load(connection)
 }
 
-pub(crate) fn update_game_to_persistent_store(kniffel_game: &KniffelGame) {
-    let connection = &mut POOL.lock().unwrap().get().unwrap();
-    {
-        connection.transaction::<_, Error, _>(|con| {
-            let option_game = load_game(con, &kniffel_game.game_id);
-            let game = option_game.expect("failed to load game");
+pub(crate) fn update_game_to_persistent_store(kniffel_game: &KniffelGame) -> Result<(), GameError> {
+    let connection = &mut get_connection()?;
+    connection.transaction::<_, Error, _>(|con| {
+        let game = load_game(con, &kniffel_game.game_id)?.ok_or(Error::NotFound)?;
 
-            update_game_to_db(con, game.id, kniffel_game);
-            update_players_to_db(con, game.id, kniffel_game);
+        update_game_to_db(con, game.id, kniffel_game)?;
+        update_players_to_db(con, game.id, kniffel_game)?;
+        insert_turns_to_db(con, game.id, &kniffel_game.turns)?;
+        insert_events_to_db(con, game.id, &kniffel_game.events)?;
 
-            Ok(())
-        }).expect("Failed to commit update game");
-    }
+        Ok(())
+    }).map_err(GameError::storage)
+}
+
+/// Replaces the stored state of a game by `kniffel_game` rebuilt from its event log. All of its turns are stored
+/// again, the events it emitted since it was rebuilt are appended to the log.
+pub(crate) fn rebuild_game_in_persistent_store(kniffel_game: &KniffelGame) -> Result<(), GameError> {
+    let connection = &mut get_connection()?;
+    connection.transaction::<_, Error, _>(|con| {
+        use crate::schema::turns::dsl::*;
+        let game = load_game(con, &kniffel_game.game_id)?.ok_or(Error::NotFound)?;
+
+        update_game_to_db(con, game.id, kniffel_game)?;
+        update_players_to_db(con, game.id, kniffel_game)?;
+        delete(turns.filter(game_id.eq(game.id))).execute(con)?;
+        insert_turns_to_db(con, game.id, &kniffel_game.turns)?;
+        insert_events_to_db(con, game.id, &kniffel_game.events)?;
+
+        Ok(())
+    }).map_err(GameError::storage)
 }
 
-fn update_game_to_db(con: &mut MysqlConnection, game_id_param: i32, kniffel_game: &KniffelGame) {
+fn update_game_to_db(con: &mut MysqlConnection, game_id_param: i32, kniffel_game: &KniffelGame) -> Result<(), Error> {
     use crate::schema::games::dsl::*;
-    let _ = update(games)
+    update(games)
         .filter(id.eq(game_id_param))
         .set((
             roll_round.eq(kniffel_game.roll_round),
@@ -159,25 +339,390 @@ fn update_game_to_db(con: &mut MysqlConnection, game_id_param: i32, kniffel_game
                 .map(|x| x.to_string())
                 .collect::<Vec<_>>()
                 .join(",")),
-            current_player.eq(kniffel_game.current_player.to_string())
+            current_player.eq(kniffel_game.current_player.to_string()),
+            dice_position.eq(kniffel_game.dice.position() as i64),
+            announcement.eq(kniffel_game.announcement.map(|bt| bt.to_string()).unwrap_or_default()),
+            turn_rolls.eq(join_dice_lists(&kniffel_game.turn_rolls)),
+            turn_kept_dice.eq(join_dice_lists(&kniffel_game.turn_kept_dice)),
+            event_count.eq(kniffel_game.event_count),
+            undo_requested_by.eq(kniffel_game.pending_undo.as_ref()
+                .map(|pending_undo| pending_undo.requested_by.to_string())
+                .unwrap_or_default()),
+            undo_approved_by.eq(kniffel_game.pending_undo.as_ref()
+                .map(|pending_undo| pending_undo.approved_by.join(","))
+                .unwrap_or_default())
         ))
-        .execute(con);
+        .execute(con)?;
+    Ok(())
 }
-fn update_players_to_db(con: &mut MysqlConnection, game_id_param: i32, kniffel_game: &KniffelGame) {
+
+fn update_players_to_db(con: &mut MysqlConnection, game_id_param: i32, kniffel_game: &KniffelGame) -> Result<(), Error> {
     use crate::schema::players::dsl::*;
 
-    kniffel_game.players.values().for_each(|player_var| {
+    for player_var in kniffel_game.players.values() {
         let planer_name = player_var.name.to_string();
-        let _ = update(players)
-            .filter(name.eq(planer_name))
+        update(players)
+            .filter(name.eq(&planer_name))
             .filter(game_id.eq(game_id_param))
             .set((
                 score.eq(player_var.score),
-                used_booking_types.eq(player_var.used_booking_types.iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join(","))
+                saved_rolls.eq(player_var.saved_rolls),
+                hint_count.eq(player_var.hint_count),
+                undo_count.eq(player_var.undo_count)
+            ))
+            .execute(con)?;
+
+        let player_id_param = players
+            .select(id)
+            .filter(name.eq(&planer_name))
+            .filter(game_id.eq(game_id_param))
+            .first::<i32>(con)?;
+        for (index, column) in player_var.columns.iter().enumerate() {
+            update_player_column_to_db(con, player_id_param, index, column)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_player_column_to_db(con: &mut MysqlConnection, player_id_param: i32, index: usize,
+                              column: &ScoreColumn) -> Result<(), Error> {
+    use crate::schema::player_columns::dsl::*;
+    update(player_columns)
+        .filter(player_id.eq(player_id_param))
+        .filter(column_index.eq(index as i32))
+        .set((
+            upper_score.eq(column.upper_score),
+            bonus.eq(column.bonus),
+            lower_score.eq(column.lower_score),
+            scored_kniffel.eq(column.scored_kniffel),
+            kniffel_bonus_count.eq(column.kniffel_bonus_count),
+            used_booking_types.eq(column.used_booking_types.iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(","))
+        ))
+        .execute(con)?;
+    Ok(())
+}
+
+/// Joins a list of dice lists as "1,2,3;4,5".
+fn join_dice_lists(dice_lists: &[Vec<i32>]) -> String {
+    dice_lists.iter()
+        .map(|dice| join_dice(dice))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Joins dice as "1,2,3".
+fn join_dice(dice: &[i32]) -> String {
+    dice.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn insert_turns_to_db(con: &mut MysqlConnection, game_id_param: i32, new_turns: &[Turn]) -> Result<(), Error> {
+    use crate::schema::turns::dsl::*;
+
+    for turn in new_turns {
+        insert_into(turns)
+            .values((
+                game_id.eq(game_id_param),
+                player_name.eq(turn.player_name.to_string()),
+                rolls.eq(join_dice_lists(&turn.rolls)),
+                kept_dice.eq(join_dice_lists(&turn.kept_dice)),
+                booking_type.eq(turn.booking_type.to_string()),
+                column_index.eq(turn.column as i32),
+                points.eq(turn.points)
             ))
-            .execute(con);
-    });
-}
\ No newline at end of file
+            .execute(con)?;
+    }
+    Ok(())
+}
+
+/// Splits "1,2,3;4,5" into a list of dice lists.
+fn split_dice_lists(dice_lists: &str) -> Result<Vec<Vec<i32>>, GameError> {
+    if dice_lists.is_empty() {
+        return Ok(Vec::new());
+    }
+    dice_lists.split(';').map(split_dice).collect()
+}
+
+/// Splits the dice kept before the re-rolls of a turn with `throws` throws. Keeping no dice is stored as an empty
+/// list, so the number of lists is taken from the throws.
+fn split_kept_dice(kept_dice_lists: &str, throws: usize) -> Result<Vec<Vec<i32>>, GameError> {
+    if throws <= 1 {
+        return Ok(Vec::new());
+    }
+    kept_dice_lists.split(';').map(split_dice).collect()
+}
+
+/// Splits "1,2,3" into dice, an empty string has no dice.
+fn split_dice(dice: &str) -> Result<Vec<i32>, GameError> {
+    dice.split(',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<i32>().map_err(GameError::storage))
+        .collect()
+}
+
+/// Stored details of a `GameCreated` event, which do not fit the columns of the other events.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "camelCase")]
+struct GameCreatedDetails {
+    players: Vec<SeatedPlayerDetails>,
+    rule_set: String,
+    columns: Vec<String>,
+    dice_seed: u64,
+    #[serde(default = "default_undo_policy")]
+    undo_policy: String,
+    #[serde(default)]
+    undo_limit: i32,
+}
+
+/// Games created before undos were introduced need consent for an undo.
+fn default_undo_policy() -> String {
+    UndoPolicy::Consent.to_string()
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct SeatedPlayerDetails {
+    name: String,
+    bot: Option<String>,
+}
+
+fn insert_events_to_db(con: &mut MysqlConnection, game_id_param: i32, events: &[GameEvent]) -> Result<(), Error> {
+    use crate::schema::game_events::dsl::*;
+
+    for event in events {
+        let (kept_dice_value, dice_value, booking_type_value, column_value, points_value) = match &event.kind {
+            GameEventKind::DiceRolled { kept_dice: kept, dice: thrown, .. } =>
+                (join_dice(kept), join_dice(thrown), String::new(), 0, 0),
+            GameEventKind::Announced { booking_type: announced, .. } =>
+                (String::new(), String::new(), announced.to_string(), 0, 0),
+            GameEventKind::CategoryBooked { booking_type: booked, column, points: gained, .. } =>
+                (String::new(), String::new(), booked.to_string(), *column as i32, *gained),
+            GameEventKind::GameCreated { .. }
+            | GameEventKind::TurnPassed { .. }
+            | GameEventKind::GameEnded
+            | GameEventKind::HintGiven { .. }
+            | GameEventKind::UndoRequested { .. }
+            | GameEventKind::UndoApproved { .. }
+            | GameEventKind::ActionUndone { .. } => (String::new(), String::new(), String::new(), 0, 0),
+        };
+        let undone_sequence_value = match &event.kind {
+            GameEventKind::ActionUndone { undone_sequence: undone, .. } => *undone,
+            _ => 0,
+        };
+        let details_value = match &event.kind {
+            GameEventKind::GameCreated { players, rule_set, columns, dice_seed, undo_policy, undo_limit } => {
+                let created = GameCreatedDetails {
+                    players: players.iter()
+                        .map(|player| SeatedPlayerDetails { name: player.name.to_string(), bot: player.bot.map(|bot| bot.to_string()) })
+                        .collect(),
+                    rule_set: rule_set.to_string(),
+                    columns: columns.iter().map(|order| order.to_string()).collect(),
+                    dice_seed: *dice_seed,
+                    undo_policy: undo_policy.to_string(),
+                    undo_limit: *undo_limit,
+                };
+                serde_json::to_string(&created).map_err(|error| Error::SerializationError(Box::new(error)))?
+            }
+            _ => String::new(),
+        };
+        insert_into(game_events)
+            .values((
+                game_id.eq(game_id_param),
+                sequence_number.eq(event.sequence),
+                created_at.eq(event.timestamp),
+                kind.eq(event.kind.name()),
+                player_name.eq(event.kind.player_name().unwrap_or_default()),
+                kept_dice.eq(kept_dice_value),
+                dice.eq(dice_value),
+                booking_type.eq(booking_type_value),
+                column_index.eq(column_value),
+                points.eq(points_value),
+                details.eq(details_value),
+                undone_sequence.eq(undone_sequence_value)
+            ))
+            .execute(con)?;
+    }
+    Ok(())
+}
+
+/// The event log of a game, oldest first.
+pub(crate) fn load_events_from_persistent_store(game_id_param: &String) -> Result<Vec<GameEvent>, GameError> {
+    let connection = &mut get_connection()?;
+    let game = load_game(connection, game_id_param)
+        .map_err(GameError::storage)?
+        .ok_or_else(|| GameError::UnknownGame(game_id_param.to_string()))?;
+
+    load_events(connection, game.id)
+        .map_err(GameError::storage)?
+        .iter()
+        .map(event_from_db)
+        .collect()
+}
+
+fn load_events(connection: &mut MysqlConnection, game_id_param: i32) -> Result<Vec<GameEventRecord>, Error> {
+    use crate::schema::game_events::dsl::*;
+    game_events
+        .select(GameEventRecord::as_select())
+        .filter(game_id.eq(game_id_param))
+        .order(sequence_number.asc())
+        .load(connection)
+}
+
+fn event_from_db(record: &GameEventRecord) -> Result<GameEvent, GameError> {
+    let player_name = record.player_name.to_string();
+    let booking_type = || BookingType::from_str(&record.booking_type)
+        .map_err(|_| GameError::Storage(format!("Unknown booking type {}", record.booking_type)));
+    let kind = match record.kind.as_str() {
+        "GAME_CREATED" => game_created_from_db(&record.details)?,
+        "DICE_ROLLED" => GameEventKind::DiceRolled {
+            player_name,
+            kept_dice: split_dice(&record.kept_dice)?,
+            dice: split_dice(&record.dice)?,
+        },
+        "ANNOUNCED" => GameEventKind::Announced { player_name, booking_type: booking_type()? },
+        "CATEGORY_BOOKED" => GameEventKind::CategoryBooked {
+            player_name,
+            booking_type: booking_type()?,
+            column: record.column_index as usize,
+            points: record.points,
+        },
+        "TURN_PASSED" => GameEventKind::TurnPassed { player_name },
+        "GAME_ENDED" => GameEventKind::GameEnded,
+        "HINT_GIVEN" => GameEventKind::HintGiven { player_name },
+        "UNDO_REQUESTED" => GameEventKind::UndoRequested { player_name },
+        "UNDO_APPROVED" => GameEventKind::UndoApproved { player_name },
+        "ACTION_UNDONE" => GameEventKind::ActionUndone { player_name, undone_sequence: record.undone_sequence },
+        other => return Err(GameError::Storage(format!("Unknown event kind {}", other))),
+    };
+    Ok(GameEvent { sequence: record.sequence_number, timestamp: record.created_at, kind })
+}
+
+fn game_created_from_db(details_value: &str) -> Result<GameEventKind, GameError> {
+    let created: GameCreatedDetails = serde_json::from_str(details_value).map_err(GameError::storage)?;
+    let players = created.players.into_iter()
+        .map(|player| {
+            let bot = player.bot
+                .map(|bot| BotStrategy::from_str(&bot).map_err(|_| GameError::Storage(format!("Unknown bot strategy {}", bot))))
+                .transpose()?;
+            Ok(SeatedPlayer { name: player.name, bot })
+        })
+        .collect::<Result<Vec<_>, GameError>>()?;
+    let columns = created.columns.iter()
+        .map(|order| ColumnOrder::from_str(order).map_err(|_| GameError::Storage(format!("Unknown column order {}", order))))
+        .collect::<Result<Vec<_>, GameError>>()?;
+    let undo_policy = UndoPolicy::from_str(&created.undo_policy)
+        .map_err(|_| GameError::Storage(format!("Unknown undo policy {}", created.undo_policy)))?;
+    Ok(GameEventKind::GameCreated {
+        players,
+        rule_set: created.rule_set,
+        columns,
+        dice_seed: created.dice_seed,
+        undo_policy,
+        undo_limit: created.undo_limit,
+    })
+}
+
+/// The seat and spectator tokens of a game, none for games created before tokens were issued.
+pub(crate) fn load_tokens_from_persistent_store(game_id_param: &String) -> Result<GameTokens, GameError> {
+    let connection = &mut get_connection()?;
+    let game = load_game(connection, game_id_param)
+        .map_err(GameError::storage)?
+        .ok_or_else(|| GameError::UnknownGame(game_id_param.to_string()))?;
+    let players = load_players(connection, game.id).map_err(GameError::storage)?;
+
+    Ok(GameTokens {
+        seats: players.into_iter()
+            .filter(|player| !player.token.is_empty())
+            .map(|player| (player.name, player.token))
+            .collect(),
+        spectator: game.spectator_token,
+    })
+}
+
+/// All finished turns of a game in the order they were played.
+pub(crate) fn load_turns_from_persistent_store(game_id_param: &String) -> Result<Vec<Turn>, GameError> {
+    let connection = &mut get_connection()?;
+    let game = load_game(connection, game_id_param)
+        .map_err(GameError::storage)?
+        .ok_or_else(|| GameError::UnknownGame(game_id_param.to_string()))?;
+
+    let records = load_turns(connection, game.id).map_err(GameError::storage)?;
+    records.iter()
+        .map(|record| Ok(Turn {
+            player_name: record.player_name.to_string(),
+            rolls: split_dice_lists(&record.rolls)?,
+            kept_dice: split_kept_dice(&record.kept_dice, record.rolls.split(';').count())?,
+            booking_type: BookingType::from_str(&record.booking_type)
+                .map_err(|_| GameError::Storage(format!("Unknown booking type {}", record.booking_type)))?,
+            column: record.column_index as usize,
+            points: record.points,
+        }))
+        .collect()
+}
+
+fn load_turns(connection: &mut MysqlConnection, game_id_param: i32) -> Result<Vec<TurnRecord>, Error> {
+    use crate::schema::turns::dsl::*;
+    turns
+        .select(TurnRecord::as_select())
+        .filter(game_id.eq(game_id_param))
+        .order(id.asc())
+        .load(connection)
+}
+
+/// Stores an update of a game for the server instances sharing the database, see `DatabaseNotifier`.
+pub(crate) fn insert_game_update(game_id_param: &str, message_param: &str) -> Result<(), GameError> {
+    use crate::schema::game_updates::dsl::*;
+    let connection = &mut get_connection()?;
+    insert_into(game_updates)
+        .values((
+            game_id.eq(game_id_param),
+            message.eq(message_param),
+            created_at.eq(now_millis()),
+        ))
+        .execute(connection)
+        .map(|_| ())
+        .map_err(GameError::storage)
+}
+
+/// Id of the latest stored game update, 0 if there is none.
+pub(crate) fn latest_game_update_id() -> Result<i32, GameError> {
+    use crate::schema::game_updates::dsl::*;
+    let connection = &mut get_connection()?;
+    game_updates
+        .select(diesel::dsl::max(id))
+        .first::<Option<i32>>(connection)
+        .map(|latest| latest.unwrap_or(0))
+        .map_err(GameError::storage)
+}
+
+/// The game updates stored after the update `after_id`, oldest first.
+pub(crate) fn load_game_updates_after(after_id: i32) -> Result<Vec<GameUpdateRecord>, GameError> {
+    use crate::schema::game_updates::dsl::*;
+    let connection = &mut get_connection()?;
+    game_updates
+        .select(GameUpdateRecord::as_select())
+        .filter(id.gt(after_id))
+        .order(id.asc())
+        .load(connection)
+        .map_err(GameError::storage)
+}
+
+/// Deletes the game updates stored longer ago than `age`, every instance has passed them on by then.
+pub(crate) fn delete_game_updates_older_than(age: Duration) -> Result<(), GameError> {
+    use crate::schema::game_updates::dsl::*;
+    let connection = &mut get_connection()?;
+    delete(game_updates.filter(created_at.lt(now_millis() - age.as_millis() as i64)))
+        .execute(connection)
+        .map(|_| ())
+        .map_err(GameError::storage)
+}
+
+/// Milliseconds since the Unix epoch.
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
@@ -0,0 +1,189 @@
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::Request;
+use std::fmt;
+use utoipa::ToSchema;
+use crate::game_logic::{BookingType, GameAction, GameState};
+
+/// Everything that can go wrong while handling a game request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// No game exists for the given game id.
+    UnknownGame(String),
+    /// The action is not allowed in the current state of the game.
+    WrongPhase { action: GameAction, state: GameState },
+    /// The current player has already booked this category.
+    CategoryAlreadyUsed(BookingType),
+    /// The category name is not known or not part of the game's rule set.
+    UnknownCategory(String),
+    /// No rule set with this name exists.
+    UnknownRuleSet(String),
+    /// The scorecard has no column with this index.
+    UnknownColumn(usize),
+    /// The options to create a game are invalid.
+    InvalidGameOptions(String),
+    /// The category cannot be booked with the dice on the table, e.g. because of the joker rules.
+    CategoryNotAllowed(BookingType),
+    /// The order of the column or the announcement of the turn does not allow this category.
+    CategoryOutOfOrder(BookingType),
+    /// A category cannot be announced at this point of the turn.
+    AnnouncementNotAllowed(String),
+    /// The dice to keep do not match the dice on the table.
+    InvalidDiceToKeep(Vec<i32>),
+    /// The positions of the dice to keep are out of range, duplicated or ambiguous.
+    InvalidPositionsToKeep(Vec<usize>),
+    /// The dice to hold are given in more than one way, or the hold mask does not have one flag per die.
+    InvalidHoldRequest(String),
+    /// The number of throws left is negative or more than the player has.
+    InvalidRollsLeft(i32),
+    /// The game cannot be analyzed, e.g. because it has not ended or its turns were not recorded.
+    AnalysisNotAvailable(String),
+    /// The solver cannot give a hint for this game, e.g. because of its rule set or columns.
+    HintNotAvailable(String),
+    /// The solver for this rule set is still computing its value table, e.g. right after the server started.
+    SolverNotReady(String),
+    /// A computer-controlled player found no move, the game cannot go on.
+    BotCannotMove(String),
+    /// The last action cannot be undone, e.g. because the next player has already acted or no undos are left.
+    UndoNotPossible(String),
+    /// The request needs a token of the game in the `X-Game-Token` header.
+    TokenMissing,
+    /// The token sent does not allow the request, e.g. because it is not the seat token of the current player.
+    TokenRejected(String),
+    /// The game cannot be played back, e.g. because it has not ended yet.
+    ReplayNotAvailable(String),
+    /// The replay step is not the sequence number of an event of the game.
+    InvalidReplayStep(i32),
+    /// The events of a game cannot be applied in their order, e.g. because the log is incomplete or corrupt.
+    EventLogInvalid(String),
+    /// The game could not be loaded from or written to the database.
+    Storage(String),
+}
+
+impl GameError {
+    /// Machine-readable code of the error, sent to the client.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::UnknownGame(_) => "UNKNOWN_GAME",
+            GameError::WrongPhase { .. } => "WRONG_PHASE",
+            GameError::CategoryAlreadyUsed(_) => "CATEGORY_ALREADY_USED",
+            GameError::UnknownCategory(_) => "UNKNOWN_CATEGORY",
+            GameError::UnknownRuleSet(_) => "UNKNOWN_RULE_SET",
+            GameError::UnknownColumn(_) => "UNKNOWN_COLUMN",
+            GameError::InvalidGameOptions(_) => "INVALID_GAME_OPTIONS",
+            GameError::CategoryNotAllowed(_) => "CATEGORY_NOT_ALLOWED",
+            GameError::CategoryOutOfOrder(_) => "CATEGORY_OUT_OF_ORDER",
+            GameError::AnnouncementNotAllowed(_) => "ANNOUNCEMENT_NOT_ALLOWED",
+            GameError::InvalidDiceToKeep(_) => "INVALID_DICE_TO_KEEP",
+            GameError::InvalidPositionsToKeep(_) => "INVALID_POSITIONS_TO_KEEP",
+            GameError::InvalidHoldRequest(_) => "INVALID_HOLD_REQUEST",
+            GameError::InvalidRollsLeft(_) => "INVALID_ROLLS_LEFT",
+            GameError::HintNotAvailable(_) => "HINT_NOT_AVAILABLE",
+            GameError::SolverNotReady(_) => "SOLVER_NOT_READY",
+            GameError::AnalysisNotAvailable(_) => "ANALYSIS_NOT_AVAILABLE",
+            GameError::BotCannotMove(_) => "BOT_CANNOT_MOVE",
+            GameError::UndoNotPossible(_) => "UNDO_NOT_POSSIBLE",
+            GameError::TokenMissing => "TOKEN_MISSING",
+            GameError::TokenRejected(_) => "TOKEN_REJECTED",
+            GameError::ReplayNotAvailable(_) => "REPLAY_NOT_AVAILABLE",
+            GameError::InvalidReplayStep(_) => "INVALID_REPLAY_STEP",
+            GameError::EventLogInvalid(_) => "EVENT_LOG_INVALID",
+            GameError::Storage(_) => "STORAGE_FAILURE",
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            GameError::UnknownGame(_) => Status::NotFound,
+            GameError::WrongPhase { .. } => Status::Conflict,
+            GameError::CategoryAlreadyUsed(_) => Status::Conflict,
+            GameError::UnknownCategory(_) => Status::BadRequest,
+            GameError::UnknownRuleSet(_) => Status::BadRequest,
+            GameError::UnknownColumn(_) => Status::BadRequest,
+            GameError::InvalidGameOptions(_) => Status::BadRequest,
+            GameError::CategoryNotAllowed(_) => Status::Conflict,
+            GameError::CategoryOutOfOrder(_) => Status::Conflict,
+            GameError::AnnouncementNotAllowed(_) => Status::Conflict,
+            GameError::InvalidDiceToKeep(_) => Status::BadRequest,
+            GameError::InvalidPositionsToKeep(_) => Status::BadRequest,
+            GameError::InvalidHoldRequest(_) => Status::BadRequest,
+            GameError::InvalidRollsLeft(_) => Status::BadRequest,
+            GameError::HintNotAvailable(_) => Status::Conflict,
+            GameError::SolverNotReady(_) => Status::ServiceUnavailable,
+            GameError::AnalysisNotAvailable(_) => Status::Conflict,
+            GameError::BotCannotMove(_) => Status::InternalServerError,
+            GameError::UndoNotPossible(_) => Status::Conflict,
+            GameError::TokenMissing => Status::Unauthorized,
+            GameError::TokenRejected(_) => Status::Forbidden,
+            GameError::ReplayNotAvailable(_) => Status::Conflict,
+            GameError::InvalidReplayStep(_) => Status::BadRequest,
+            GameError::EventLogInvalid(_) => Status::InternalServerError,
+            GameError::Storage(_) => Status::InternalServerError,
+        }
+    }
+
+    /// Wraps any database error into a `GameError::Storage`.
+    pub fn storage(error: impl fmt::Display) -> Self {
+        GameError::Storage(error.to_string())
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::UnknownGame(game_id) => write!(f, "Game {} not found", game_id),
+            GameError::WrongPhase { action, state } => write!(f, "Cannot {} while the game is in state {}", action, state),
+            GameError::CategoryAlreadyUsed(booking_type) => write!(f, "BookingType {} already used", booking_type),
+            GameError::UnknownCategory(booking_type) => write!(f, "Unknown BookingType {}", booking_type),
+            GameError::UnknownRuleSet(rule_set) => write!(f, "Unknown rule set {}", rule_set),
+            GameError::UnknownColumn(column) => write!(f, "Unknown column {}", column),
+            GameError::InvalidGameOptions(message) => write!(f, "Invalid game options: {}", message),
+            GameError::CategoryNotAllowed(booking_type) => write!(f, "BookingType {} not allowed for these dice", booking_type),
+            GameError::CategoryOutOfOrder(booking_type) => write!(f, "BookingType {} cannot be booked in this order", booking_type),
+            GameError::AnnouncementNotAllowed(message) => write!(f, "Announcement not allowed: {}", message),
+            GameError::InvalidDiceToKeep(dice) => write!(f, "Dice to keep {:?} are not on the table", dice),
+            GameError::InvalidPositionsToKeep(positions) => write!(f, "Positions to keep {:?} are invalid", positions),
+            GameError::InvalidHoldRequest(message) => write!(f, "Invalid dice to hold: {}", message),
+            GameError::InvalidRollsLeft(rolls_left) => write!(f, "Rolls left {} are not possible in this turn", rolls_left),
+            GameError::HintNotAvailable(message) => write!(f, "No hint available: {}", message),
+            GameError::SolverNotReady(rule_set) => write!(f, "The solver for {} is not ready yet, try again shortly", rule_set),
+            GameError::AnalysisNotAvailable(message) => write!(f, "No analysis available: {}", message),
+            GameError::BotCannotMove(player_name) => write!(f, "Bot {} cannot move", player_name),
+            GameError::UndoNotPossible(message) => write!(f, "Undo not possible: {}", message),
+            GameError::TokenMissing => write!(f, "Token of the game missing"),
+            GameError::TokenRejected(message) => write!(f, "Token rejected: {}", message),
+            GameError::ReplayNotAvailable(message) => write!(f, "No replay available: {}", message),
+            GameError::InvalidReplayStep(step) => write!(f, "Replay step {} is not in the event log", step),
+            GameError::EventLogInvalid(message) => write!(f, "Invalid event log: {}", message),
+            GameError::Storage(message) => write!(f, "Storage failure: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// Problem response body sent for every `GameError`.
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemResponse {
+    status: u16,
+    code: String,
+    detail: String,
+}
+
+impl<'r> Responder<'r, 'static> for GameError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let problem = ProblemResponse {
+            status: self.status().code,
+            code: self.code().to_string(),
+            detail: self.to_string(),
+        };
+        Response::build_from(Json(problem).respond_to(request)?)
+            .status(self.status())
+            .header(ContentType::new("application", "problem+json"))
+            .ok()
+    }
+}
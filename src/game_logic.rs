@@ -1,11 +1,17 @@
 
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use rocket::serde::{Deserialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::str::FromStr;
-use crate::models::{Game, Player};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::bot::BotStrategy;
+use crate::game_error::GameError;
+use crate::rule_set::{rule_set_by_name, RuleSet, KNIFFEL};
 use crate::scoring::*;
+use crate::solver::Decision;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -16,11 +22,18 @@ pub enum BookingType {
     Fours,
     Fives,
     Sixes,
+    OnePair,
+    TwoPairs,
+    ThreePairs,
     ThreeOfAKind,
     FourOfAKind,
+    FiveOfAKind,
     FullHouse,
+    Castle,
+    Tower,
     SmallStraight,
     LargeStraight,
+    FullStraight,
     Kniffel,
     Chance,
 }
@@ -35,17 +48,41 @@ impl fmt::Display for BookingType {
             BookingType::Fours => "FOURS",
             BookingType::Fives => "FIVES",
             BookingType::Sixes => "SIXES",
+            BookingType::OnePair => "ONE_PAIR",
+            BookingType::TwoPairs => "TWO_PAIRS",
+            BookingType::ThreePairs => "THREE_PAIRS",
             BookingType::ThreeOfAKind => "THREE_OF_A_KIND",
             BookingType::FourOfAKind => "FOUR_OF_A_KIND",
+            BookingType::FiveOfAKind => "FIVE_OF_A_KIND",
             BookingType::FullHouse => "FULL_HOUSE",
+            BookingType::Castle => "CASTLE",
+            BookingType::Tower => "TOWER",
             BookingType::SmallStraight => "SMALL_STRAIGHT",
             BookingType::LargeStraight => "LARGE_STRAIGHT",
+            BookingType::FullStraight => "FULL_STRAIGHT",
             BookingType::Kniffel => "KNIFFEL",
             BookingType::Chance => "CHANCE",
         })
     }
 }
 
+/// ONES..SIXES in dice value order.
+pub const UPPER_SECTION: [BookingType; 6] = [
+    BookingType::Ones,
+    BookingType::Twos,
+    BookingType::Threes,
+    BookingType::Fours,
+    BookingType::Fives,
+    BookingType::Sixes,
+];
+
+impl BookingType {
+    /// Returns true for ONES..SIXES, the categories counting towards the upper-section bonus.
+    pub fn is_upper_section(&self) -> bool {
+        UPPER_SECTION.contains(self)
+    }
+}
+
 impl FromStr for BookingType {
     type Err = ();
 
@@ -57,11 +94,18 @@ impl FromStr for BookingType {
             "FOURS" => Ok(BookingType::Fours),
             "FIVES" => Ok(BookingType::Fives),
             "SIXES" => Ok(BookingType::Sixes),
+            "ONE_PAIR" => Ok(BookingType::OnePair),
+            "TWO_PAIRS" => Ok(BookingType::TwoPairs),
+            "THREE_PAIRS" => Ok(BookingType::ThreePairs),
             "THREE_OF_A_KIND" => Ok(BookingType::ThreeOfAKind),
             "FOUR_OF_A_KIND" => Ok(BookingType::FourOfAKind),
+            "FIVE_OF_A_KIND" => Ok(BookingType::FiveOfAKind),
             "FULL_HOUSE" => Ok(BookingType::FullHouse),
+            "CASTLE" => Ok(BookingType::Castle),
+            "TOWER" => Ok(BookingType::Tower),
             "SMALL_STRAIGHT" => Ok(BookingType::SmallStraight),
             "LARGE_STRAIGHT" => Ok(BookingType::LargeStraight),
+            "FULL_STRAIGHT" => Ok(BookingType::FullStraight),
             "KNIFFEL" => Ok(BookingType::Kniffel),
             "CHANCE" => Ok(BookingType::Chance),
             _ => Err(()),
@@ -71,16 +115,28 @@ impl FromStr for BookingType {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 pub enum GameState {
-    /// The player has to select dice they want to keep. Check the round if this is the first or second re-roll phase.
+    /// The player can select dice they want to keep and re-roll, or book the dice on the table right away.
+    /// Check the round if this is the first or second re-roll phase.
     Roll,
 
-    /// The player has to book the dice on the table into one category.
+    /// All throws of the turn are used, the player has to book the dice on the table into one category.
     Book,
 
     /// The game has ended
     Ended,
 }
 
+impl GameState {
+    /// The actions a player may take in this state.
+    pub fn allowed_actions(&self) -> &'static [GameAction] {
+        match self {
+            GameState::Roll => &[GameAction::Roll, GameAction::Book, GameAction::Announce],
+            GameState::Book => &[GameAction::Book],
+            GameState::Ended => &[],
+        }
+    }
+}
+
 impl fmt::Display for GameState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -104,27 +160,268 @@ impl FromStr for GameState {
     }
 }
 
+/// Actions a player can take on a game, which ones are allowed depends on the `GameState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameAction {
+    /// Re-roll some or all dice
+    Roll,
+
+    /// Book the dice on the table into one category
+    Book,
+
+    /// Declare the category of an announced column after the first throw
+    Announce,
+}
+
+impl fmt::Display for GameAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameAction::Roll => write!(f, "roll"),
+            GameAction::Book => write!(f, "book"),
+            GameAction::Announce => write!(f, "announce"),
+        }
+    }
+}
+
+/// Order in which the categories of a scorecard column have to be filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnOrder {
+    /// Any open category
+    Free,
+
+    /// Top-down in the order of the rule set's categories
+    Down,
+
+    /// Bottom-up in the order of the rule set's categories
+    Up,
+
+    /// Only the category announced after the first throw of the turn.
+    /// Without an announcement a category can only be crossed out with 0 points.
+    Announced,
+}
+
+impl fmt::Display for ColumnOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColumnOrder::Free => write!(f, "FREE"),
+            ColumnOrder::Down => write!(f, "DOWN"),
+            ColumnOrder::Up => write!(f, "UP"),
+            ColumnOrder::Announced => write!(f, "ANNOUNCED"),
+        }
+    }
+}
+
+impl FromStr for ColumnOrder {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FREE" => Ok(ColumnOrder::Free),
+            "DOWN" => Ok(ColumnOrder::Down),
+            "UP" => Ok(ColumnOrder::Up),
+            "ANNOUNCED" => Ok(ColumnOrder::Announced),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Who decides whether a player may undo their last action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UndoPolicy {
+    /// Every other human player has to approve the undo
+    Consent,
+
+    /// Every player may undo a limited number of times without approval
+    Limited,
+}
+
+impl fmt::Display for UndoPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UndoPolicy::Consent => write!(f, "CONSENT"),
+            UndoPolicy::Limited => write!(f, "LIMITED"),
+        }
+    }
+}
+
+impl FromStr for UndoPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CONSENT" => Ok(UndoPolicy::Consent),
+            "LIMITED" => Ok(UndoPolicy::Limited),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Number of throws per turn, including the initial roll.
+pub const MAX_ROLLS_PER_TURN: i32 = 3;
+
+/// Source of the dice faces rolled in a game.
+pub trait DiceSource: fmt::Debug + Send {
+    /// Rolls a single die and returns a value between 1 and 6 (inclusive).
+    fn roll_die(&mut self) -> i32;
+
+    /// The seed this source was started with.
+    fn seed(&self) -> u64;
+
+    /// The number of dice rolled from this source so far.
+    fn position(&self) -> u64;
+
+    fn box_clone(&self) -> Box<dyn DiceSource>;
+}
+
+impl Clone for Box<dyn DiceSource> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// Pseudo-random dice from a seeded RNG. Seed and position restore the exact same stream.
 #[derive(Debug, Clone)]
-pub struct KniffelPlayer {
-    pub(crate) name: String,
-    pub(crate) score: i32,
-    /** each bookingType can only be used once per game */
-    pub(crate) used_booking_types: HashSet<BookingType>,
+pub struct SeededDice {
+    seed: u64,
+    position: u64,
+    rng: StdRng,
 }
 
-impl KniffelPlayer {
+impl SeededDice {
+    pub fn new(seed: u64) -> Self {
+        SeededDice {
+            seed,
+            position: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Creates a source with a random seed.
+    pub fn from_random_seed() -> Self {
+        SeededDice::new(rand::thread_rng().gen())
+    }
+
+    /// Restores a source with `seed` that has already rolled `position` dice.
+    pub fn resume(seed: u64, position: u64) -> Self {
+        let mut dice = SeededDice::new(seed);
+        for _ in 0..position {
+            dice.roll_die();
+        }
+        dice
+    }
+}
+
+impl DiceSource for SeededDice {
+    fn roll_die(&mut self) -> i32 {
+        self.position += 1;
+        self.rng.gen_range(1..=6) // Generates a random number between 1 and 6 (inclusive)
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn box_clone(&self) -> Box<dyn DiceSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// Dice from a fixed sequence of values, starting over once the sequence is used up.
+/// Not used by the server itself, meant for deterministic tests.
+#[derive(Debug, Clone)]
+pub struct ScriptedDice {
+    values: Vec<i32>,
+    position: u64,
+}
+
+impl ScriptedDice {
+    pub fn new(values: Vec<i32>) -> Self {
+        assert!(!values.is_empty(), "ScriptedDice needs at least one value");
+        ScriptedDice { values, position: 0 }
+    }
+}
+
+impl DiceSource for ScriptedDice {
+    fn roll_die(&mut self) -> i32 {
+        let value = self.values[(self.position % self.values.len() as u64) as usize];
+        self.position += 1;
+        value
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn box_clone(&self) -> Box<dyn DiceSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// One column of a player's scorecard, its total counts `multiplier` times towards the player's score.
+#[derive(Debug, Clone)]
+pub struct ScoreColumn {
+    pub multiplier: i32,
+    /** order in which the categories have to be filled */
+    pub order: ColumnOrder,
+    /** sum of ONES..SIXES */
+    pub upper_score: i32,
+    /** upper-section bonus of the rule set, awarded once upper_score reaches the threshold */
+    pub bonus: i32,
+    /** sum of all categories below SIXES, including extra Kniffel bonuses */
+    pub lower_score: i32,
+    /** true once the KNIFFEL box was booked with points, only then extra Kniffels earn a bonus */
+    pub scored_kniffel: bool,
+    /** number of extra Kniffels that earned a bonus */
+    pub kniffel_bonus_count: i32,
+    /** each bookingType can only be used once per column */
+    pub used_booking_types: HashSet<BookingType>,
+}
+
+impl ScoreColumn {
     // Constructor
-    pub fn new(name: &String) -> Self {
-        KniffelPlayer {
-            name: name.to_string(),
-            score: 0,
+    pub fn new(multiplier: i32, order: ColumnOrder) -> Self {
+        ScoreColumn {
+            multiplier,
+            order,
+            upper_score: 0,
+            bonus: 0,
+            lower_score: 0,
+            scored_kniffel: false,
+            kniffel_bonus_count: 0,
             used_booking_types: HashSet::new(),
         }
     }
 
-    // add_score
-    pub fn add_score(&mut self, score: i32) {
-        self.score += score;
+    /// Unweighted total of the column: upper subtotal + bonus + lower subtotal.
+    pub fn total(&self) -> i32 {
+        self.upper_score + self.bonus + self.lower_score
+    }
+
+    // add_score to the section of the booking type and re-evaluate the upper-section bonus
+    pub fn add_score(&mut self, booking_type: BookingType, score: i32, rule_set: &dyn RuleSet) {
+        if booking_type.is_upper_section() {
+            self.upper_score += score;
+        } else {
+            self.lower_score += score;
+        }
+        if booking_type == BookingType::Kniffel && score > 0 {
+            self.scored_kniffel = true;
+        }
+        self.bonus = rule_set.upper_bonus(self.upper_score);
+    }
+
+    // add_kniffel_bonus for an extra Kniffel, it counts towards the lower section
+    pub fn add_kniffel_bonus(&mut self, bonus_points: i32) {
+        self.kniffel_bonus_count += 1;
+        self.lower_score += bonus_points;
     }
 
     // Method to add a booking type to used_booking_types
@@ -137,136 +434,993 @@ impl KniffelPlayer {
         self.used_booking_types.contains(booking_type)
     }
 
-    fn from(player: &Player) -> Self {
-        let used_booking_types = player
-            .used_booking_types
-            .split(',')
-            .filter_map(|s| s.parse::<BookingType>().ok())
-            .collect::<HashSet<_>>();
+    /// The open categories that may be booked into this column next, given the announcement of the turn.
+    /// An announced column without an announcement accepts every open category, crossed out with 0 points.
+    pub fn bookable_types(&self, rule_set: &dyn RuleSet, announcement: Option<BookingType>) -> Vec<BookingType> {
+        let mut open_types = rule_set.categories().iter()
+            .filter(|booking_type| !self.has_used_booking_type(booking_type))
+            .copied();
+        match (self.order, announcement) {
+            (ColumnOrder::Down, _) => open_types.next().into_iter().collect(),
+            (ColumnOrder::Up, _) => open_types.next_back().into_iter().collect(),
+            (ColumnOrder::Announced, Some(announced)) => open_types
+                .filter(|&booking_type| booking_type == announced)
+                .collect(),
+            (ColumnOrder::Free, _) | (ColumnOrder::Announced, None) => open_types.collect(),
+        }
+    }
 
+}
+
+#[derive(Debug, Clone)]
+pub struct KniffelPlayer {
+    pub name: String,
+    /** position in the turn order, starting at 0 */
+    pub seat: i32,
+    /** total score: sum of the column totals, each times its multiplier */
+    pub score: i32,
+    /** unused throws carried over to later turns, only if the rule set saves rolls */
+    pub saved_rolls: i32,
+    /** number of hints the player asked for */
+    pub hint_count: i32,
+    /** number of the player's actions that were undone */
+    pub undo_count: i32,
+    /** strategy of a computer-controlled player, None for a human */
+    pub bot: Option<BotStrategy>,
+    /** scorecard columns, column i has the multiplier i + 1 */
+    pub columns: Vec<ScoreColumn>,
+}
+
+impl KniffelPlayer {
+    // Constructor
+    pub fn new(name: &String) -> Self {
         KniffelPlayer {
-            name: player.name.clone(),
-            score: player.score,
-            used_booking_types,
+            name: name.to_string(),
+            seat: 0,
+            score: 0,
+            saved_rolls: 0,
+            hint_count: 0,
+            undo_count: 0,
+            bot: None,
+            columns: vec![ScoreColumn::new(1, ColumnOrder::Free)],
         }
     }
+
+    // Constructor for a computer-controlled player
+    pub fn bot(name: &String, strategy: BotStrategy) -> Self {
+        KniffelPlayer {
+            bot: Some(strategy),
+            ..KniffelPlayer::new(name)
+        }
+    }
+
+    // Method to recalculate the total score after a column changed
+    pub fn update_score(&mut self) {
+        self.score = self.weighted_sum(ScoreColumn::total);
+    }
+
+    /// Sums a value over all columns, each times its multiplier.
+    pub fn weighted_sum(&self, value: impl Fn(&ScoreColumn) -> i32) -> i32 {
+        self.columns.iter()
+            .map(|column| value(column) * column.multiplier)
+            .sum()
+    }
+
+    // Method to check if a booking type has been used in every column
+    pub fn has_used_booking_type(&self, booking_type: &BookingType) -> bool {
+        self.columns.iter().all(|column| column.has_used_booking_type(booking_type))
+    }
+
 }
 
-fn convert_players(players: &[Player]) -> HashMap<String, KniffelPlayer> {
-    players.iter().map(|p| (p.name.clone(), KniffelPlayer::from(p))).collect()
+/// Options chosen when a game is created.
+#[derive(Debug, Clone)]
+pub struct GameOptions {
+    pub rule_set: &'static dyn RuleSet,
+    /// order of each scorecard column per player, three columns for Triple Kniffel
+    pub columns: Vec<ColumnOrder>,
+    /// seat the players in random order instead of list order
+    pub shuffle_seats: bool,
+    pub undo_policy: UndoPolicy,
+    /// undos per player if the undo policy is limited
+    pub undo_limit: i32,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            rule_set: &KNIFFEL,
+            columns: vec![ColumnOrder::Free],
+            shuffle_seats: false,
+            undo_policy: UndoPolicy::Consent,
+            undo_limit: 0,
+        }
+    }
+}
+
+/// A finished turn of a player.
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub player_name: String,
+    /// dice after every throw of the turn
+    pub rolls: Vec<Vec<i32>>,
+    /// dice kept before every re-roll
+    pub kept_dice: Vec<Vec<i32>>,
+    pub booking_type: BookingType,
+    pub column: usize,
+    /// points the player's score gained
+    pub points: i32,
+}
+
+/// A player as seated when the game was created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeatedPlayer {
+    pub name: String,
+    pub bot: Option<BotStrategy>,
+}
+
+/// A player's request to undo their last action, waiting for the approval of the other human players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingUndo {
+    pub requested_by: String,
+    pub approved_by: Vec<String>,
+}
+
+/// What happened in a game, see `GameEvent`. Folding the events of a game in order rebuilds its state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEventKind {
+    /// The game was created for `players` in seat order, the first player is to move
+    GameCreated {
+        players: Vec<SeatedPlayer>,
+        rule_set: String,
+        columns: Vec<ColumnOrder>,
+        dice_seed: u64,
+        undo_policy: UndoPolicy,
+        undo_limit: i32,
+    },
+
+    /// The player threw every die except `kept_dice`, `dice` lie on the table afterwards
+    DiceRolled { player_name: String, kept_dice: Vec<i32>, dice: Vec<i32> },
+
+    /// The player announced the category they will book into an announced column this turn
+    Announced { player_name: String, booking_type: BookingType },
+
+    /// The player booked the dice on the table. `points` is what their score gained when the game was played;
+    /// the points are computed again by the current rules when the event is applied.
+    CategoryBooked { player_name: String, booking_type: BookingType, column: usize, points: i32 },
+
+    /// The turn passed to the player
+    TurnPassed { player_name: String },
+
+    /// Every scorecard is full
+    GameEnded,
+
+    /// The player was given a hint
+    HintGiven { player_name: String },
+
+    /// The player asked to undo their last action
+    UndoRequested { player_name: String },
+
+    /// The player approved the pending undo
+    UndoApproved { player_name: String },
+
+    /// The last action of the player was taken back: every roll, announcement, booking and turn change from
+    /// `undone_sequence` on is left out when the events are folded
+    ActionUndone { player_name: String, undone_sequence: i32 },
+}
+
+impl GameEventKind {
+    /// Name of the kind of event, sent to clients and stored with the event.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEventKind::GameCreated { .. } => "GAME_CREATED",
+            GameEventKind::DiceRolled { .. } => "DICE_ROLLED",
+            GameEventKind::Announced { .. } => "ANNOUNCED",
+            GameEventKind::CategoryBooked { .. } => "CATEGORY_BOOKED",
+            GameEventKind::TurnPassed { .. } => "TURN_PASSED",
+            GameEventKind::GameEnded => "GAME_ENDED",
+            GameEventKind::HintGiven { .. } => "HINT_GIVEN",
+            GameEventKind::UndoRequested { .. } => "UNDO_REQUESTED",
+            GameEventKind::UndoApproved { .. } => "UNDO_APPROVED",
+            GameEventKind::ActionUndone { .. } => "ACTION_UNDONE",
+        }
+    }
+
+    /// The player the event is about, None for events about the whole game.
+    pub fn player_name(&self) -> Option<&str> {
+        match self {
+            GameEventKind::DiceRolled { player_name, .. }
+            | GameEventKind::Announced { player_name, .. }
+            | GameEventKind::CategoryBooked { player_name, .. }
+            | GameEventKind::TurnPassed { player_name }
+            | GameEventKind::HintGiven { player_name }
+            | GameEventKind::UndoRequested { player_name }
+            | GameEventKind::UndoApproved { player_name }
+            | GameEventKind::ActionUndone { player_name, .. } => Some(player_name),
+            GameEventKind::GameCreated { .. } | GameEventKind::GameEnded => None,
+        }
+    }
+
+    /// True for the events of playing the game, which an undo can take back.
+    fn is_play(&self) -> bool {
+        matches!(self, GameEventKind::DiceRolled { .. }
+            | GameEventKind::Announced { .. }
+            | GameEventKind::CategoryBooked { .. }
+            | GameEventKind::TurnPassed { .. }
+            | GameEventKind::GameEnded)
+    }
+}
+
+/// An entry of a game's append-only event log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameEvent {
+    /// position in the game's event log, starting at 0
+    pub sequence: i32,
+    /// milliseconds since the Unix epoch
+    pub timestamp: i64,
+    pub kind: GameEventKind,
+}
+
+/// The events that count when `events` are folded: the plays taken back by an undo are left out.
+fn effective_events(events: &[GameEvent]) -> Vec<&GameEvent> {
+    let mut effective: Vec<&GameEvent> = Vec::new();
+    for event in events {
+        if let GameEventKind::ActionUndone { undone_sequence, .. } = &event.kind {
+            effective.retain(|kept| kept.sequence < *undone_sequence || !kept.kind.is_play());
+        }
+        effective.push(event);
+    }
+    effective
+}
+
+/// The sequence number of the last roll or booking and the player who made it, None if there is none or the next
+/// player has already acted. The first throw of the next player is rolled for them, so it does not count as acting.
+fn undo_target(events: &[&GameEvent]) -> Option<(i32, String)> {
+    let mut plays = events.iter().rev().filter(|event| event.kind.is_play());
+    let last = plays.next()?;
+    let booking = match &last.kind {
+        GameEventKind::GameEnded => plays.next()?,
+        GameEventKind::DiceRolled { player_name, .. } => match plays.next() {
+            Some(before) if matches!(before.kind, GameEventKind::TurnPassed { .. }) => plays.next()?,
+            Some(_) => return Some((last.sequence, player_name.to_string())),
+            // the first throw of the game
+            None => return None,
+        },
+        _ => return None,
+    };
+    match &booking.kind {
+        GameEventKind::CategoryBooked { player_name, .. } => Some((booking.sequence, player_name.to_string())),
+        _ => None,
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+/// Maximum number of scorecard columns per player.
+pub const MAX_COLUMNS: usize = 6;
+
+impl GameOptions {
+    /// Checks that the options describe a playable game for `players`. There must be at least one player and
+    /// every player needs a name of their own, since players are told apart by their names.
+    pub fn validate(&self, players: &[KniffelPlayer]) -> Result<(), GameError> {
+        if players.is_empty() {
+            return Err(GameError::InvalidGameOptions("at least one player is needed".to_string()));
+        }
+        let mut names = HashSet::new();
+        for player in players {
+            if player.name.trim().is_empty() {
+                return Err(GameError::InvalidGameOptions("player names must not be empty".to_string()));
+            }
+            // the approvals of an undo are stored as a comma separated list of names
+            if player.name.contains(',') {
+                return Err(GameError::InvalidGameOptions(format!("player name {} must not contain a comma", player.name)));
+            }
+            if !names.insert(player.name.as_str()) {
+                return Err(GameError::InvalidGameOptions(format!("player name {} is used twice", player.name)));
+            }
+        }
+        if !(1..=MAX_COLUMNS).contains(&self.columns.len()) {
+            return Err(GameError::InvalidGameOptions(
+                format!("columnCount must be between 1 and {}", MAX_COLUMNS)));
+        }
+        if self.undo_limit < 0 {
+            return Err(GameError::InvalidGameOptions("undoLimit must not be negative".to_string()));
+        }
+        Ok(())
+    }
 }
 
 
 #[derive(Debug, Clone)]
 pub struct KniffelGame {
-    pub(crate) players: HashMap<String, KniffelPlayer>,
-    pub(crate) game_id: String,
-    pub(crate) roll_round: i32,
-    pub(crate) current_player: String,
-    pub(crate) state: GameState,
-    pub(crate) dice_rolls: [i32; 5],
+    pub players: HashMap<String, KniffelPlayer>,
+    pub game_id: String,
+    pub roll_round: i32,
+    pub current_player: String,
+    pub state: GameState,
+    pub dice_rolls: Vec<i32>,
+    pub rule_set: &'static dyn RuleSet,
+    /// number of scorecard columns per player
+    pub column_count: usize,
+    /// category the current player announced this turn for an announced column
+    pub announcement: Option<BookingType>,
+    pub dice: Box<dyn DiceSource>,
+    /// turns finished since the game was created or loaded
+    pub turns: Vec<Turn>,
+    /// dice after every throw of the current turn so far
+    pub turn_rolls: Vec<Vec<i32>>,
+    /// dice kept before every re-roll of the current turn so far
+    pub turn_kept_dice: Vec<Vec<i32>>,
+    /// events since the game was created or loaded
+    pub events: Vec<GameEvent>,
+    /// number of events in the game's log, including the ones stored before it was loaded
+    pub event_count: i32,
+    pub undo_policy: UndoPolicy,
+    /// undos per player if the undo policy is limited
+    pub undo_limit: i32,
+    /// undo waiting for approval
+    pub pending_undo: Option<PendingUndo>,
 }
 
 impl KniffelGame {
     /// Creates a new KniffelGame for a list of players and starts the game by performing the first dice roll for the starting player.
-    pub fn new(player_list: Vec<KniffelPlayer>) -> Self {
+    /// The game is played by the rule set of `options` with one scorecard per column. Players are seated in
+    /// list order, or in random order if `shuffle_seats` is set. All dice are rolled from `dice`.
+    /// If bots are seated first, their turns are played right away. Fails if the options are not valid for the
+    /// players, see `GameOptions::validate`, or if one of the bots cannot move.
+    pub fn new(mut player_list: Vec<KniffelPlayer>, options: GameOptions, dice: Box<dyn DiceSource>) -> Result<Self, GameError> {
+        options.validate(&player_list)?;
         let game_id = uuid::Uuid::new_v4().to_string().replace("-", "");
-        let mut players = HashMap::new();
-
-        for player in player_list.iter() {
-            players.insert(player.name.clone(), player.clone());
+        if options.shuffle_seats {
+            player_list.shuffle(&mut rand::thread_rng());
         }
 
-        let current_player = player_list[0].clone();
-        let mut game = KniffelGame {
-            players,
-            game_id,
+        let mut game = KniffelGame::empty(&game_id, dice);
+        let created = GameEventKind::GameCreated {
+            players: player_list.iter()
+                .map(|player| SeatedPlayer { name: player.name.to_string(), bot: player.bot })
+                .collect(),
+            rule_set: options.rule_set.name().to_string(),
+            columns: options.columns.clone(),
+            dice_seed: game.dice.seed(),
+            undo_policy: options.undo_policy,
+            undo_limit: options.undo_limit,
+        };
+        game.emit(created)?;
+        game.roll_dice(&[])?;
+        game.play_bot_turns()?;
+        Ok(game)
+    }
+
+    /// A game without players, the state before its `GameCreated` event.
+    fn empty(game_id: &str, dice: Box<dyn DiceSource>) -> Self {
+        KniffelGame {
+            players: HashMap::new(),
+            game_id: game_id.to_string(),
             roll_round: 0,
-            current_player: current_player.name,
+            current_player: String::new(),
             state: GameState::Roll,
-            dice_rolls: [0; 5],
-        };
-        let empty_array: [i32; 0] = [];
-        game.re_roll_dice(&empty_array);
-        game
-    }
-
-    pub fn from_db(game: &Game, players: &[Player]) -> Self {
-        let kniffel_players_map = convert_players(&players);
-
-        let result: [i32; 5] = game.dice_rolls
-            .split(',')
-            .map(|s| s.parse::<i32>().unwrap()) // Parse each segment as i32
-            .collect::<Vec<i32>>()
-            .try_into() // Convert Vec<i32> to [i32; 5]
-            .expect("Expected a list of 5 elements");
-
-        let game = KniffelGame {
-            players: kniffel_players_map,
-            game_id: game.game_id.to_string(),
-            roll_round: game.roll_round,
-            current_player: game.current_player.to_string(),
-            state: GameState::from_str(&game.stage.to_string()).unwrap(),
-            dice_rolls: result,
+            dice_rolls: Vec::new(),
+            rule_set: &KNIFFEL,
+            column_count: 0,
+            announcement: None,
+            dice,
+            turns: Vec::new(),
+            turn_rolls: Vec::new(),
+            turn_kept_dice: Vec::new(),
+            events: Vec::new(),
+            event_count: 0,
+            undo_policy: UndoPolicy::Consent,
+            undo_limit: 0,
+            pending_undo: None,
+        }
+    }
+
+    /// Rebuilds a game by applying its events in order, e.g. a prefix of its log to see the game as it was
+    /// after the last of them. Bookings are scored by the current rules, so fixed scoring rules change the totals.
+    /// Later dice are rolled from the seed of the game, continuing after the dice of the events, including undone ones.
+    pub fn from_events(game_id: &str, events: &[GameEvent]) -> Result<Self, GameError> {
+        let dice_seed = match events.first().map(|event| &event.kind) {
+            Some(GameEventKind::GameCreated { dice_seed, .. }) => *dice_seed,
+            _ => return Err(GameError::EventLogInvalid("the log does not start with the creation of the game".to_string())),
         };
-        game
+        let mut game = KniffelGame::empty(game_id, Box::new(SeededDice::new(dice_seed)));
+        for event in effective_events(events) {
+            game.apply(&event.kind).map_err(|error| match error {
+                GameError::EventLogInvalid(message) => GameError::EventLogInvalid(format!("event {}: {}", event.sequence, message)),
+                error => GameError::EventLogInvalid(format!("event {}: {}", event.sequence, error)),
+            })?;
+        }
+        let thrown_dice: usize = events.iter()
+            .map(|event| match &event.kind {
+                GameEventKind::DiceRolled { kept_dice, dice, .. } => dice.len().saturating_sub(kept_dice.len()),
+                _ => 0,
+            })
+            .sum();
+        game.dice = Box::new(SeededDice::resume(dice_seed, thrown_dice as u64));
+        game.event_count = events.last().map_or(0, |event| event.sequence + 1);
+        Ok(game)
     }
 
-    /// Re-rolls all, some, or no dice.
-    pub fn re_roll_dice(&mut self, dice_to_keep: &[i32]) {
-        self.remove_dice(&dice_to_keep);
-        let mut rng = rand::thread_rng();
-        for value in self.dice_rolls.iter_mut() {
-            if *value == 0 {
-                *value = rng.gen_range(1..=6); // Generates a random number between 1 and 6 (inclusive)
+    /// Changes the state of the game by one event. Checks that the event is possible in the current state, but
+    /// leaves the validation of the player's choices to the commands that emit the events. An event that does not
+    /// fit the game, e.g. one of an unknown player, is an `EventLogInvalid`.
+    fn apply(&mut self, kind: &GameEventKind) -> Result<(), GameError> {
+        if let Some(player_name) = kind.player_name() {
+            if !self.players.contains_key(player_name) {
+                return Err(GameError::EventLogInvalid(format!("unknown player {}", player_name)));
+            }
+        }
+        match kind {
+            GameEventKind::GameCreated { players, rule_set, columns, undo_policy, undo_limit, .. } => {
+                let rule_set = rule_set_by_name(rule_set)
+                    .ok_or_else(|| GameError::EventLogInvalid(format!("unknown rule set {}", rule_set)))?;
+                let first_player = players.first()
+                    .ok_or_else(|| GameError::EventLogInvalid("the game was created without players".to_string()))?;
+                self.players = players.iter()
+                    .enumerate()
+                    .map(|(seat, seated)| {
+                        let mut player = KniffelPlayer::new(&seated.name);
+                        player.seat = seat as i32;
+                        player.bot = seated.bot;
+                        player.columns = columns.iter()
+                            .enumerate()
+                            .map(|(index, &order)| ScoreColumn::new(index as i32 + 1, order))
+                            .collect();
+                        (seated.name.to_string(), player)
+                    })
+                    .collect();
+                self.current_player = first_player.name.to_string();
+                self.rule_set = rule_set;
+                self.column_count = columns.len();
+                self.dice_rolls = vec![0; rule_set.dice_count()];
+                self.roll_round = 0;
+                self.state = GameState::Roll;
+                self.undo_policy = *undo_policy;
+                self.undo_limit = *undo_limit;
+            }
+            GameEventKind::DiceRolled { player_name, kept_dice, dice } => {
+                self.check_current_player(player_name)?;
+                self.check_action(GameAction::Roll)?;
+                self.pending_undo = None;
+                self.dice_rolls = dice.clone();
+                self.roll_round += 1;
+                // a first throw starts a new turn
+                if self.roll_round == 1 {
+                    self.turn_rolls.clear();
+                    self.turn_kept_dice.clear();
+                } else {
+                    self.turn_kept_dice.push(kept_dice.clone());
+                }
+                self.turn_rolls.push(dice.clone());
+                if self.roll_round >= self.max_rolls() {
+                    self.state = GameState::Book;
+                }
+            }
+            GameEventKind::Announced { player_name, booking_type } => {
+                self.check_current_player(player_name)?;
+                self.check_action(GameAction::Announce)?;
+                self.pending_undo = None;
+                self.announcement = Some(*booking_type);
+            }
+            GameEventKind::CategoryBooked { player_name, booking_type, column, .. } => {
+                self.check_current_player(player_name)?;
+                self.check_action(GameAction::Book)?;
+                self.pending_undo = None;
+                let (to_add_score, kniffel_bonus) = self.booking_points(*booking_type, *column)?;
+                let unused_rolls = self.max_rolls() - self.roll_round;
+                let rule_set = self.rule_set;
+                let player = self.players.get_mut(player_name)
+                    .ok_or_else(|| GameError::EventLogInvalid(format!("unknown player {}", player_name)))?;
+                let score_before = player.score;
+                let score_column = &mut player.columns[*column];
+                score_column.add_score(*booking_type, to_add_score, rule_set);
+                if kniffel_bonus > 0 {
+                    score_column.add_kniffel_bonus(kniffel_bonus);
+                }
+                score_column.use_booking_type(*booking_type);
+                player.update_score();
+                if rule_set.saves_rolls() {
+                    player.saved_rolls = unused_rolls;
+                }
+                let points = player.score - score_before;
+                self.turns.push(Turn {
+                    player_name: player_name.to_string(),
+                    rolls: std::mem::take(&mut self.turn_rolls),
+                    kept_dice: std::mem::take(&mut self.turn_kept_dice),
+                    booking_type: *booking_type,
+                    column: *column,
+                    points,
+                });
+            }
+            GameEventKind::TurnPassed { player_name } => {
+                self.current_player = player_name.to_string();
+                self.announcement = None;
+                self.roll_round = 0;
+                self.state = GameState::Roll;
+            }
+            GameEventKind::GameEnded => {
+                self.announcement = None;
+                self.state = GameState::Ended;
+            }
+            GameEventKind::HintGiven { player_name } => {
+                if let Some(player) = self.players.get_mut(player_name) {
+                    player.hint_count += 1;
+                }
+            }
+            GameEventKind::UndoRequested { player_name } => {
+                self.pending_undo = Some(PendingUndo { requested_by: player_name.to_string(), approved_by: Vec::new() });
+            }
+            GameEventKind::UndoApproved { player_name } => {
+                let pending_undo = self.pending_undo.as_mut()
+                    .ok_or_else(|| GameError::EventLogInvalid("an undo was approved that was not requested".to_string()))?;
+                pending_undo.approved_by.push(player_name.to_string());
+            }
+            GameEventKind::ActionUndone { player_name, .. } => {
+                if let Some(player) = self.players.get_mut(player_name) {
+                    player.undo_count += 1;
+                }
+                self.pending_undo = None;
             }
         }
-        self.dice_rolls.sort();
-        self.roll_round += 1;
+        Ok(())
+    }
 
-        if self.roll_round == 3 {
-            self.next_phase();
+    /// Applies an event and appends it to the game's log.
+    fn emit(&mut self, kind: GameEventKind) -> Result<(), GameError> {
+        self.apply(&kind)?;
+        self.events.push(GameEvent { sequence: self.event_count, timestamp: now_millis(), kind });
+        self.event_count += 1;
+        Ok(())
+    }
+
+    fn check_current_player(&self, player_name: &str) -> Result<(), GameError> {
+        if self.current_player == player_name {
+            Ok(())
+        } else {
+            Err(GameError::EventLogInvalid(format!("{} is not the current player", player_name)))
         }
     }
 
-    /// Books the current dice into a booking type. Each booking type must only be used once.
-    pub fn book_dice_roll(&mut self, booking_type: BookingType) {
-        let to_add_score = self.get_to_add_score(booking_type);
+    /// Re-rolls all, some, or no dice. The dice to keep are given by value and the dice are sorted afterwards.
+    pub fn re_roll_dice(&mut self, dice_to_keep: &[i32]) -> Result<(), GameError> {
+        self.check_action(GameAction::Roll)?;
+        if !self.are_dice_on_table(dice_to_keep) {
+            return Err(GameError::InvalidDiceToKeep(dice_to_keep.to_vec()));
+        }
+        self.roll_dice(dice_to_keep)
+    }
 
-        if let Some(player) = self.players.get_mut(&self.current_player) {
-            if player.has_used_booking_type(&booking_type) {
-                panic!("BookingType already used");
-            }
-            player.add_score(to_add_score);
-            player.use_booking_type(booking_type);
+    /// Returns an error unless `action` is allowed in the current state.
+    pub fn check_action(&self, action: GameAction) -> Result<(), GameError> {
+        if self.state.allowed_actions().contains(&action) {
+            Ok(())
         } else {
-            println!("Player not found!");
+            Err(GameError::WrongPhase { action, state: self.state })
         }
+    }
 
-        self.next_phase();
+    pub fn can_roll(&self) -> bool {
+        self.check_action(GameAction::Roll).is_ok()
     }
 
-    fn next_phase(&mut self) {
-        self.state = if self.state == GameState::Roll {
-            GameState::Book
+    pub fn can_book(&self) -> bool {
+        self.check_action(GameAction::Book).is_ok()
+    }
+
+    /// True if the current player may announce a category right now.
+    pub fn can_announce(&self) -> bool {
+        self.check_action(GameAction::Announce).is_ok()
+            && self.roll_round == 1
+            && self.announcement.is_none()
+            && self.players.get(&self.current_player)
+                .is_some_and(|player| player.columns.iter().any(|column| column.order == ColumnOrder::Announced))
+    }
+
+    /// Announces the category the current player will book into an announced column this turn,
+    /// only possible right after the first throw. Afterwards the turn must end with booking that category
+    /// into an announced column.
+    pub fn announce(&mut self, booking_type: BookingType) -> Result<(), GameError> {
+        self.check_action(GameAction::Announce)?;
+        if !self.rule_set.categories().contains(&booking_type) {
+            return Err(GameError::UnknownCategory(booking_type.to_string()));
+        }
+        if !self.can_announce() {
+            return Err(GameError::AnnouncementNotAllowed(
+                "a category can only be announced once, right after the first throw".to_string()));
+        }
+        let open_in_announced_column = self.players.get(&self.current_player)
+            .is_some_and(|player| player.columns.iter()
+                .any(|column| column.order == ColumnOrder::Announced && !column.has_used_booking_type(&booking_type)));
+        if !open_in_announced_column {
+            return Err(GameError::CategoryAlreadyUsed(booking_type));
+        }
+        self.emit(GameEventKind::Announced { player_name: self.current_player.to_string(), booking_type })
+    }
+
+    /// Number of re-rolls the current player has left this turn.
+    pub fn rolls_left(&self) -> i32 {
+        if self.can_roll() {
+            self.max_rolls() - self.roll_round
         } else {
-            GameState::Roll
+            0
+        }
+    }
+
+    /// Number of throws the current player has this turn, including their saved throws if the rule set carries them over.
+    pub fn max_rolls(&self) -> i32 {
+        let saved_rolls = match self.players.get(&self.current_player) {
+            Some(player) if self.rule_set.saves_rolls() => player.saved_rolls,
+            _ => 0,
         };
+        MAX_ROLLS_PER_TURN + saved_rolls
+    }
 
-        if self.state == GameState::Roll {
-            let next_player = self.find_next_player();
-            self.current_player = next_player.name;
-            if next_player.used_booking_types.len() == 13 {
-                self.state = GameState::Ended;
-            } else {
-                self.roll_round = 0;
-                let empty_array: [i32; 0] = [];
-                self.re_roll_dice(&empty_array);
+    /// True if every value in `dice_to_keep` can be taken from a different die on the table.
+    fn are_dice_on_table(&self, dice_to_keep: &[i32]) -> bool {
+        let mut freq_map = HashMap::new();
+        for &num in self.dice_rolls.iter() {
+            *freq_map.entry(num).or_insert(0) += 1;
+        }
+        dice_to_keep.iter().all(|val| match freq_map.get_mut(val) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Re-rolls all dice except the ones at `positions_to_keep`. The dice are not sorted, so every die keeps its position.
+    pub fn re_roll_dice_at_positions(&mut self, positions_to_keep: &[usize]) -> Result<(), GameError> {
+        self.check_action(GameAction::Roll)?;
+        let mut unique_positions = positions_to_keep.to_vec();
+        unique_positions.sort_unstable();
+        unique_positions.dedup();
+        if unique_positions.len() != positions_to_keep.len()
+            || positions_to_keep.iter().any(|&position| position >= self.dice_rolls.len()) {
+            return Err(GameError::InvalidPositionsToKeep(positions_to_keep.to_vec()));
+        }
+        let dice_to_keep = positions_to_keep.iter().map(|&position| self.dice_rolls[position]).collect();
+        let mut dice = self.dice_rolls.clone();
+        for (position, value) in dice.iter_mut().enumerate() {
+            if !positions_to_keep.contains(&position) {
+                *value = 0;
+            }
+        }
+        self.throw(dice_to_keep, dice)
+    }
+
+    fn roll_dice(&mut self, dice_to_keep: &[i32]) -> Result<(), GameError> {
+        let mut dice = self.remove_dice(dice_to_keep);
+        for value in dice.iter_mut().filter(|value| **value == 0) {
+            *value = self.dice.roll_die();
+        }
+        dice.sort();
+        self.emit(GameEventKind::DiceRolled {
+            player_name: self.current_player.to_string(),
+            kept_dice: dice_to_keep.to_vec(),
+            dice,
+        })
+    }
+
+    /// Rolls every die of `dice` set to 0, the others stay in place.
+    fn throw(&mut self, dice_to_keep: Vec<i32>, mut dice: Vec<i32>) -> Result<(), GameError> {
+        for value in dice.iter_mut().filter(|value| **value == 0) {
+            *value = self.dice.roll_die();
+        }
+        self.emit(GameEventKind::DiceRolled { player_name: self.current_player.to_string(), kept_dice: dice_to_keep, dice })
+    }
+
+    /// Books the current dice into a booking type, possible after any throw of the turn. Each booking type must only be
+    /// used once per column and in the order of the column. Without a `column` the first column where this
+    /// booking type may be booked is used. After an announcement only the announced booking type can be booked,
+    /// into an announced column.
+    /// An extra Kniffel earns the bonus of the game's rule set and is played as a joker if the rule set has joker rules.
+    /// If the next players are bots, their turns are played right away. If one of them cannot move, the error is
+    /// returned and the game must be dropped, the booking and the bot turns before are not taken back.
+    pub fn book_dice_roll(&mut self, booking_type: BookingType, column: Option<usize>) -> Result<(), GameError> {
+        self.book(booking_type, column)?;
+        self.play_bot_turns()
+    }
+
+    fn book(&mut self, booking_type: BookingType, column: Option<usize>) -> Result<(), GameError> {
+        self.check_action(GameAction::Book)?;
+        if !self.rule_set.categories().contains(&booking_type) {
+            return Err(GameError::UnknownCategory(booking_type.to_string()));
+        }
+        let column = self.resolve_column(booking_type, column)?;
+        self.booking_points(booking_type, column)?;
+        let multiplier = self.current_column(column).map_or(1, |score_column| score_column.multiplier);
+        let points = self.potential_score(booking_type, column).unwrap_or(0) * multiplier;
+
+        self.emit(GameEventKind::CategoryBooked {
+            player_name: self.current_player.to_string(),
+            booking_type,
+            column,
+            points,
+        })?;
+        self.next_phase()
+    }
+
+    /// The column to book `booking_type` into: `column` if it exists, otherwise the first column where it may be booked now.
+    fn resolve_column(&self, booking_type: BookingType, column: Option<usize>) -> Result<usize, GameError> {
+        match column {
+            Some(column) if column >= self.column_count => Err(GameError::UnknownColumn(column)),
+            Some(column) => Ok(column),
+            None => match self.first_bookable_column(booking_type) {
+                Some(column) => Ok(column),
+                None if self.first_open_column(booking_type).is_some() => Err(GameError::CategoryOutOfOrder(booking_type)),
+                None => Err(GameError::CategoryAlreadyUsed(booking_type)),
+            },
+        }
+    }
+
+    /// The score for booking the dice on the table into `booking_type` of `column` and the extra Kniffel bonus
+    /// it earns, or the reason why this booking is not allowed.
+    fn booking_points(&self, booking_type: BookingType, column: usize) -> Result<(i32, i32), GameError> {
+        let score_column = self.current_column(column).ok_or(GameError::UnknownColumn(column))?;
+        if score_column.has_used_booking_type(&booking_type) {
+            return Err(GameError::CategoryAlreadyUsed(booking_type));
+        }
+        if !self.is_bookable(booking_type, column) {
+            return Err(GameError::CategoryOutOfOrder(booking_type));
+        }
+        if self.is_crossed_out(column) {
+            return Ok((0, 0));
+        }
+        if !self.is_joker_booking_allowed(booking_type, column) {
+            return Err(GameError::CategoryNotAllowed(booking_type));
+        }
+        Ok((self.get_to_add_score(booking_type, column), self.get_kniffel_bonus(column)))
+    }
+
+    /// Points the column total of the current player would gain by booking the dice on the table into
+    /// `booking_type` of `column`, including a reached upper-section bonus and an extra Kniffel bonus.
+    /// None if the booking is not allowed.
+    pub fn potential_score(&self, booking_type: BookingType, column: usize) -> Option<i32> {
+        if !self.can_book() {
+            return None;
+        }
+        let (to_add_score, kniffel_bonus) = self.booking_points(booking_type, column).ok()?;
+        let mut score_column = self.current_column(column)?.clone();
+        let total_before = score_column.total();
+        score_column.add_score(booking_type, to_add_score, self.rule_set);
+        if kniffel_bonus > 0 {
+            score_column.add_kniffel_bonus(kniffel_bonus);
+        }
+        Some(score_column.total() - total_before)
+    }
+
+    /// Points the current player's score would gain for every booking type that can be booked without choosing a
+    /// column, in the order of the rule set. Empty if nothing can be booked.
+    pub fn potential_scores(&self) -> Vec<(BookingType, i32)> {
+        self.rule_set.categories()
+            .iter()
+            .filter_map(|&booking_type| {
+                let column = self.resolve_column(booking_type, None).ok()?;
+                let multiplier = self.current_column(column)?.multiplier;
+                Some((booking_type, self.potential_score(booking_type, column)? * multiplier))
+            })
+            .collect()
+    }
+
+    /// Every booking the current player may make now as booking type, column and points their score would gain.
+    pub fn booking_options(&self) -> Vec<(BookingType, usize, i32)> {
+        (0..self.column_count)
+            .flat_map(|column| self.bookable_types(column)
+                .into_iter()
+                .filter_map(move |booking_type| {
+                    let multiplier = self.current_column(column)?.multiplier;
+                    Some((booking_type, column, self.potential_score(booking_type, column)? * multiplier))
+                }))
+            .collect()
+    }
+
+    /// Chances of the current player to end the turn with each dice pattern and the expected points of every
+    /// category still open in one of their columns, if `dice_to_keep` are kept from the dice on the table.
+    /// `rolls_left` defaults to the throws the player has left and may not exceed them. The expected points are
+    /// the category's own points without bonuses, joker rules or column multipliers.
+    pub fn turn_odds(&self, dice_to_keep: &[i32], rolls_left: Option<i32>)
+                     -> Result<(PatternProbabilities, Vec<(BookingType, f64)>), GameError> {
+        self.check_action(GameAction::Book)?;
+        if !self.are_dice_on_table(dice_to_keep) {
+            return Err(GameError::InvalidDiceToKeep(dice_to_keep.to_vec()));
+        }
+        let rolls_left = match rolls_left {
+            Some(rolls_left) if !(0..=self.rolls_left()).contains(&rolls_left) => return Err(GameError::InvalidRollsLeft(rolls_left)),
+            Some(rolls_left) => rolls_left,
+            None => self.rolls_left(),
+        };
+        let player = self.players.get(&self.current_player).ok_or_else(|| GameError::UnknownGame(self.game_id.to_string()))?;
+
+        let open_types: Vec<BookingType> = self.rule_set.categories()
+            .iter()
+            .copied()
+            .filter(|booking_type| player.columns.iter().any(|column| !column.has_used_booking_type(booking_type)))
+            .collect();
+        // dice that are not thrown again keep counting as served
+        let served = self.roll_round == 1 && (rolls_left == 0 || dice_to_keep.len() == self.dice_rolls.len());
+        let scores: Vec<_> = open_types.iter()
+            .map(|&booking_type| move |dice: &[i32]| self.rule_set.score(booking_type, dice, served) as f64)
+            .collect();
+        let targets: Vec<TurnTarget> = scores.iter().map(|score| score as TurnTarget).collect();
+        let expected_scores = expected_turn_values(&self.dice_rolls, dice_to_keep, rolls_left, &targets);
+
+        Ok((
+            pattern_probabilities(&self.dice_rolls, dice_to_keep, rolls_left),
+            open_types.into_iter().zip(expected_scores).collect(),
+        ))
+    }
+
+    /// Plays the turns of computer-controlled players until a human is to move or the game has ended.
+    fn play_bot_turns(&mut self) -> Result<(), GameError> {
+        while self.state != GameState::Ended {
+            let strategy = match self.players.get(&self.current_player).and_then(|player| player.bot) {
+                Some(strategy) => strategy,
+                None => return Ok(()),
+            };
+            self.play_bot_turn(strategy)?;
+        }
+        Ok(())
+    }
+
+    /// Plays one turn of the current player with `strategy`, `BotCannotMove` if there was nothing to book.
+    fn play_bot_turn(&mut self, strategy: BotStrategy) -> Result<(), GameError> {
+        loop {
+            let decision = strategy.decide(self);
+            if let Some(Decision::Keep(dice_to_keep)) = &decision {
+                if self.re_roll_dice(dice_to_keep).is_ok() {
+                    continue;
+                }
             }
+
+            // book the chosen booking type into its best column, or the best booking if the decision is not possible
+            let options = self.booking_options();
+            let chosen = match decision {
+                Some(Decision::Book(booking_type)) => options.iter()
+                    .filter(|(bt, _, _)| *bt == booking_type)
+                    .max_by_key(|(_, _, points)| *points),
+                _ => None,
+            };
+            let (booking_type, column, _) = match chosen.or_else(|| options.iter().max_by_key(|(_, _, points)| *points)) {
+                Some(&option) => option,
+                None => return Err(GameError::BotCannotMove(self.current_player.to_string())),
+            };
+            return self.book(booking_type, Some(column));
         }
     }
 
-    fn remove_dice(&mut self, dice_to_keep: &[i32]) {
+    /// True if `player_name` is a computer-controlled player of this game.
+    pub fn is_bot(&self, player_name: &str) -> bool {
+        self.players.get(player_name).is_some_and(|player| player.bot.is_some())
+    }
+
+    /// Counts a hint given to the current player.
+    pub fn record_hint(&mut self) -> Result<(), GameError> {
+        self.emit(GameEventKind::HintGiven { player_name: self.current_player.to_string() })
+    }
+
+    /// Takes back the last roll or booking of the game as long as the next player has not acted, e.g. after a misclick.
+    /// The game must have been rebuilt from `log` with `from_events`.
+    ///
+    /// Under the consent policy the player who made the move asks for the undo and every other human player
+    /// approves it by calling this in turn; the move is taken back with the last approval. Under the limited policy
+    /// the move is taken back right away while the player has undos left. Dice thrown again after an undo are new dice.
+    pub fn undo(&mut self, log: &[GameEvent], player_name: &str) -> Result<(), GameError> {
+        if !self.players.contains_key(player_name) {
+            return Err(GameError::UndoNotPossible(format!("unknown player {}", player_name)));
+        }
+        let history: Vec<GameEvent> = log.iter().chain(self.events.iter()).cloned().collect();
+        let (undone_sequence, owner) = undo_target(&effective_events(&history))
+            .ok_or_else(|| GameError::UndoNotPossible("there is no roll or booking left to undo in this turn".to_string()))?;
+        if self.is_bot(&owner) {
+            return Err(GameError::UndoNotPossible(format!("the bot {} has played since", owner)));
+        }
+
+        match (self.undo_policy, &self.pending_undo) {
+            (UndoPolicy::Consent, Some(pending_undo)) => {
+                if pending_undo.requested_by == player_name || pending_undo.approved_by.iter().any(|name| name == player_name) {
+                    return Err(GameError::UndoNotPossible(format!("{} already agreed to the undo", player_name)));
+                }
+                if self.is_bot(player_name) {
+                    return Err(GameError::UndoNotPossible(format!("{} is a bot", player_name)));
+                }
+                self.emit(GameEventKind::UndoApproved { player_name: player_name.to_string() })?;
+            }
+            (policy, _) => {
+                if owner != player_name {
+                    return Err(GameError::UndoNotPossible(format!("only {} can undo their last move", owner)));
+                }
+                let undo_count = self.players.get(player_name).map_or(0, |player| player.undo_count);
+                if policy == UndoPolicy::Limited && undo_count >= self.undo_limit {
+                    return Err(GameError::UndoNotPossible(format!("{} has no undos left", player_name)));
+                }
+                if policy == UndoPolicy::Consent {
+                    self.emit(GameEventKind::UndoRequested { player_name: player_name.to_string() })?;
+                }
+            }
+        }
+        if !self.missing_undo_approvals().is_empty() {
+            return Ok(());
+        }
+
+        self.emit(GameEventKind::ActionUndone { player_name: owner, undone_sequence })?;
+        let history: Vec<GameEvent> = log.iter().chain(self.events.iter()).cloned().collect();
+        let mut rebuilt = KniffelGame::from_events(&self.game_id, &history)?;
+        rebuilt.events = std::mem::take(&mut self.events);
+        *self = rebuilt;
+        Ok(())
+    }
+
+    /// The human players who still have to approve the pending undo, bots always agree.
+    pub fn missing_undo_approvals(&self) -> Vec<String> {
+        let pending_undo = match &self.pending_undo {
+            Some(pending_undo) => pending_undo,
+            None => return Vec::new(),
+        };
+        self.seated_players()
+            .iter()
+            .filter(|player| player.bot.is_none()
+                && player.name != pending_undo.requested_by
+                && !pending_undo.approved_by.contains(&player.name))
+            .map(|player| player.name.to_string())
+            .collect()
+    }
+
+    /// The first column of the current player where `booking_type` is still open.
+    fn first_open_column(&self, booking_type: BookingType) -> Option<usize> {
+        self.players.get(&self.current_player)?
+            .columns
+            .iter()
+            .position(|column| !column.has_used_booking_type(&booking_type))
+    }
+
+    /// The first column of the current player where `booking_type` may be booked now.
+    fn first_bookable_column(&self, booking_type: BookingType) -> Option<usize> {
+        (0..self.column_count).find(|&column| self.is_bookable(booking_type, column))
+    }
+
+    /// The booking types the current player may book into `column` now, respecting the column order and the announcement.
+    pub fn bookable_types(&self, column: usize) -> Vec<BookingType> {
+        let score_column = match self.current_column(column) {
+            Some(score_column) => score_column,
+            None => return Vec::new(),
+        };
+        match self.announcement {
+            Some(_) if score_column.order != ColumnOrder::Announced => Vec::new(),
+            announcement => score_column.bookable_types(self.rule_set, announcement),
+        }
+    }
+
+    /// True if the current player may book `booking_type` into `column` now.
+    fn is_bookable(&self, booking_type: BookingType, column: usize) -> bool {
+        self.bookable_types(column).contains(&booking_type)
+    }
+
+    /// True if a booking into `column` is crossed out with 0 points: an announced column without an announcement.
+    fn is_crossed_out(&self, column: usize) -> bool {
+        self.announcement.is_none()
+            && self.current_column(column).is_some_and(|score_column| score_column.order == ColumnOrder::Announced)
+    }
+
+    /// Passes the turn to the next player after a booking and performs their first roll, or ends the game.
+    fn next_phase(&mut self) -> Result<(), GameError> {
+        let next_player = self.find_next_player();
+        if self.rule_set.is_finished(&next_player) {
+            return self.emit(GameEventKind::GameEnded);
+        }
+        self.emit(GameEventKind::TurnPassed { player_name: next_player.name })?;
+        self.roll_dice(&[])
+    }
+
+    /// The dice on the table with every die not in `dice_to_keep` set to 0.
+    fn remove_dice(&self, dice_to_keep: &[i32]) -> Vec<i32> {
         // Create a frequency map for dice
         let mut freq_map = HashMap::new();
         for &num in self.dice_rolls.iter() {
@@ -274,7 +1428,7 @@ impl KniffelGame {
         }
 
         // Initialize the result with 0s
-        let mut result = [0; 5];
+        let mut result = vec![0; self.dice_rolls.len()];
 
         // Iterate through values and update result based on freq_map
         for (i, &val) in dice_to_keep.iter().enumerate() {
@@ -286,41 +1440,343 @@ impl KniffelGame {
             }
         }
 
-        // Copy result back to dice
-        self.dice_rolls.copy_from_slice(&result);
+        result
+    }
+
+    /// Returns all players in seat order.
+    pub fn seated_players(&self) -> Vec<&KniffelPlayer> {
+        let mut seated_players: Vec<&KniffelPlayer> = self.players.values().collect();
+        seated_players.sort_by_key(|player| player.seat);
+        seated_players
     }
 
     fn find_next_player(&self) -> KniffelPlayer {
-        let mut player_iterator = self.players.values();
+        let seated_players = self.seated_players();
+        let position = seated_players.iter()
+            .position(|player| player.name.eq(&self.current_player))
+            .expect("No next player found");
 
-        while let Some(player) = player_iterator.next() {
-            if player.name.eq(&self.current_player) {
-                let ret_obj = player_iterator.next();
-                if ret_obj.is_none() {
-                    return self.players.values().next().expect("player not found").clone();
-                }
-                return ret_obj.unwrap().clone();
+        seated_players[(position + 1) % seated_players.len()].clone()
+    }
+
+    /// The column of the current player's scorecard.
+    fn current_column(&self, column: usize) -> Option<&ScoreColumn> {
+        self.players.get(&self.current_player)?.columns.get(column)
+    }
+
+    /// True if the rule set has joker rules and the dice show a Kniffel while the KNIFFEL box of the current player's
+    /// column is already used.
+    fn is_joker(&self, column: usize) -> bool {
+        self.rule_set.has_joker_rules()
+            && is_kniffel(&self.dice_rolls)
+            && self.current_column(column)
+                .is_some_and(|score_column| score_column.has_used_booking_type(&BookingType::Kniffel))
+    }
+
+    /// Bonus points for an extra Kniffel, 0 if there is none or the KNIFFEL box of the column was booked with 0.
+    fn get_kniffel_bonus(&self, column: usize) -> i32 {
+        let scored_kniffel = self.current_column(column)
+            .is_some_and(|score_column| score_column.scored_kniffel);
+        if self.is_joker(column) && scored_kniffel {
+            self.rule_set.extra_kniffel_bonus()
+        } else {
+            0
+        }
+    }
+
+    /// Forced joker rule: an extra Kniffel must go into the matching upper box if it is open,
+    /// otherwise into any open lower box, and only if those are all used into another upper box.
+    /// Only the boxes the column order allows right now are considered open.
+    fn is_joker_booking_allowed(&self, booking_type: BookingType, column: usize) -> bool {
+        if !self.is_joker(column) {
+            return true;
+        }
+        let bookable_types = self.bookable_types(column);
+        let matching_upper_box = UPPER_SECTION[(self.dice_rolls[0] - 1) as usize];
+        if bookable_types.contains(&matching_upper_box) {
+            return booking_type == matching_upper_box;
+        }
+        let lower_box_open = bookable_types.iter().any(|bt| !bt.is_upper_section());
+        !booking_type.is_upper_section() || !lower_box_open
+    }
+
+    fn get_to_add_score(&self, booking_type: BookingType, column: usize) -> i32 {
+        if self.is_joker(column) {
+            self.rule_set.joker_score(booking_type, &self.dice_rolls)
+        } else {
+            self.rule_set.score(booking_type, &self.dice_rolls, self.roll_round == 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_set::YAHTZEE;
+
+    fn humans(names: &[&str]) -> Vec<KniffelPlayer> {
+        names.iter().map(|name| KniffelPlayer::new(&name.to_string())).collect()
+    }
+
+    fn scripted_game(names: &[&str], dice: Vec<i32>, options: GameOptions) -> KniffelGame {
+        KniffelGame::new(humans(names), options, Box::new(ScriptedDice::new(dice))).unwrap()
+    }
+
+    /// Plays the current human player's moves with the heuristic strategy until `moves` are made or the game ends.
+    fn play(game: &mut KniffelGame, moves: usize) {
+        for _ in 0..moves {
+            match BotStrategy::Heuristic.decide(game) {
+                Some(Decision::Keep(dice_to_keep)) => game.re_roll_dice(&dice_to_keep).unwrap(),
+                Some(Decision::Book(booking_type)) => game.book_dice_roll(booking_type, None).unwrap(),
+                None => return,
             }
         }
+    }
 
-        panic!("No next player found");
+    fn scores(game: &KniffelGame) -> Vec<(String, i32)> {
+        game.seated_players().iter().map(|player| (player.name.to_string(), player.score)).collect()
     }
 
-    fn get_to_add_score(&self, booking_type: BookingType) -> i32 {
-        match booking_type {
-            BookingType::Ones => get_score_1_to_6(&self.dice_rolls, 1),
-            BookingType::Twos => get_score_1_to_6(&self.dice_rolls, 2),
-            BookingType::Threes => get_score_1_to_6(&self.dice_rolls, 3),
-            BookingType::Fours => get_score_1_to_6(&self.dice_rolls, 4),
-            BookingType::Fives => get_score_1_to_6(&self.dice_rolls, 5),
-            BookingType::Sixes => get_score_1_to_6(&self.dice_rolls, 6),
-            BookingType::ThreeOfAKind => get_score_for_x_of_a_kind(&self.dice_rolls, 3),
-            BookingType::FourOfAKind => get_score_for_x_of_a_kind(&self.dice_rolls, 4),
-            BookingType::Kniffel => get_score_kniffel(&self.dice_rolls),
-            BookingType::FullHouse => get_score_full_house(&self.dice_rolls),
-            BookingType::SmallStraight => get_score_small_straight(&self.dice_rolls),
-            BookingType::LargeStraight => get_score_large_straight(&self.dice_rolls),
-            BookingType::Chance => get_score_chance(&self.dice_rolls),
+    fn assert_same_game(rebuilt: &KniffelGame, game: &KniffelGame) {
+        assert_eq!(scores(rebuilt), scores(game));
+        assert_eq!(rebuilt.current_player, game.current_player);
+        assert_eq!(rebuilt.state, game.state);
+        assert_eq!(rebuilt.roll_round, game.roll_round);
+        assert_eq!(rebuilt.dice_rolls, game.dice_rolls);
+        assert_eq!(rebuilt.turns.len(), game.turns.len());
+    }
+
+    #[test]
+    fn seeded_dice_resume_where_they_stopped() {
+        let mut dice = SeededDice::new(42);
+        for _ in 0..7 {
+            assert!((1..=6).contains(&dice.roll_die()));
+        }
+        let mut resumed = SeededDice::resume(42, 7);
+        assert_eq!((resumed.seed(), resumed.position()), (42, 7));
+        assert_eq!((0..5).map(|_| resumed.roll_die()).collect::<Vec<_>>(), (0..5).map(|_| dice.roll_die()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn scripted_dice_start_over() {
+        let mut dice = ScriptedDice::new(vec![1, 2]);
+        assert_eq!((0..5).map(|_| dice.roll_die()).collect::<Vec<_>>(), vec![1, 2, 1, 2, 1]);
+    }
+
+    #[test]
+    fn player_names_must_be_unique_and_not_empty() {
+        let options = GameOptions::default();
+        assert!(options.validate(&humans(&["Ann", "Bob"])).is_ok());
+        assert!(matches!(options.validate(&humans(&["Ann", " "])), Err(GameError::InvalidGameOptions(_))));
+        let mut players = humans(&["Ann"]);
+        players.push(KniffelPlayer::bot(&"Ann".to_string(), BotStrategy::Greedy));
+        assert!(matches!(options.validate(&players), Err(GameError::InvalidGameOptions(_))));
+    }
+
+    #[test]
+    fn player_names_must_not_contain_a_comma() {
+        assert!(matches!(GameOptions::default().validate(&humans(&["Ann", "Bob, Cid"])), Err(GameError::InvalidGameOptions(_))));
+    }
+
+    #[test]
+    fn a_game_without_players_is_not_started() {
+        let result = KniffelGame::new(Vec::new(), GameOptions::default(), Box::new(SeededDice::new(1)));
+        assert!(matches!(result, Err(GameError::InvalidGameOptions(_))));
+    }
+
+    #[test]
+    fn upper_bonus_is_awarded_at_the_threshold() {
+        let mut column = ScoreColumn::new(1, ColumnOrder::Free);
+        column.add_score(BookingType::Sixes, 30, &KNIFFEL);
+        column.add_score(BookingType::Fives, 25, &KNIFFEL);
+        assert_eq!(column.bonus, 0);
+        column.add_score(BookingType::Fours, 8, &KNIFFEL);
+        assert_eq!((column.upper_score, column.bonus, column.total()), (63, 35, 98));
+    }
+
+    #[test]
+    fn roll_then_book_passes_the_turn() {
+        let mut game = scripted_game(&["Ann", "Bob"], vec![1, 2, 3, 4, 6], GameOptions::default());
+        assert_eq!(game.dice_rolls, vec![1, 2, 3, 4, 6]);
+        game.re_roll_dice(&[1, 2, 3, 4]).unwrap();
+        assert_eq!(game.roll_round, 2);
+        assert!(matches!(game.re_roll_dice(&[5]), Err(GameError::InvalidDiceToKeep(_))));
+        game.book_dice_roll(BookingType::SmallStraight, None).unwrap();
+        assert_eq!(game.players["Ann"].score, 30);
+        assert_eq!(game.current_player, "Bob");
+        assert_eq!(game.roll_round, 1);
+    }
+
+    #[test]
+    fn third_throw_leaves_only_booking() {
+        let mut game = scripted_game(&["Ann"], vec![2], GameOptions::default());
+        game.re_roll_dice(&[]).unwrap();
+        game.re_roll_dice(&[]).unwrap();
+        assert_eq!(game.state, GameState::Book);
+        assert!(matches!(game.re_roll_dice(&[]), Err(GameError::WrongPhase { .. })));
+    }
+
+    #[test]
+    fn extra_kniffel_is_a_forced_joker_with_bonus() {
+        let mut game = scripted_game(&["Ann"], vec![6], GameOptions::default());
+        game.book_dice_roll(BookingType::Kniffel, None).unwrap();
+        assert_eq!(game.players["Ann"].score, 50);
+
+        // the matching upper box is open, so it has to be used
+        assert_eq!(game.book_dice_roll(BookingType::FullHouse, None), Err(GameError::CategoryNotAllowed(BookingType::FullHouse)));
+        game.book_dice_roll(BookingType::Sixes, None).unwrap();
+        assert_eq!(game.players["Ann"].score, 50 + 30 + 50);
+
+        // then any open lower box, scored as a joker, before another upper box
+        assert_eq!(game.book_dice_roll(BookingType::Ones, None), Err(GameError::CategoryNotAllowed(BookingType::Ones)));
+        game.book_dice_roll(BookingType::LargeStraight, None).unwrap();
+        let column = &game.players["Ann"].columns[0];
+        assert_eq!(game.players["Ann"].score, 130 + 40 + 50);
+        assert_eq!(column.kniffel_bonus_count, 2);
+    }
+
+    #[test]
+    fn extra_kniffel_earns_no_bonus_after_a_crossed_out_kniffel_box() {
+        let options = GameOptions { rule_set: &YAHTZEE, ..GameOptions::default() };
+        let mut game = scripted_game(&["Ann"], vec![1, 2, 3, 4, 6, 5, 5, 5, 5, 5], options);
+        game.book_dice_roll(BookingType::Kniffel, None).unwrap();
+        assert_eq!(game.dice_rolls, vec![5, 5, 5, 5, 5]);
+        game.book_dice_roll(BookingType::Fives, None).unwrap();
+        assert_eq!(game.players["Ann"].score, 25);
+        assert_eq!(game.players["Ann"].columns[0].kniffel_bonus_count, 0);
+    }
+
+    #[test]
+    fn limited_undo_takes_back_a_roll_until_no_undos_are_left() {
+        let options = GameOptions { undo_policy: UndoPolicy::Limited, undo_limit: 1, ..GameOptions::default() };
+        let mut game = scripted_game(&["Ann", "Bob"], vec![1, 2, 3, 4, 6, 5], options);
+        let served = game.dice_rolls.clone();
+        game.re_roll_dice(&[1, 2, 3, 4]).unwrap();
+
+        assert!(matches!(game.undo(&[], "Bob"), Err(GameError::UndoNotPossible(_))));
+        game.undo(&[], "Ann").unwrap();
+        assert_eq!((game.roll_round, game.dice_rolls.clone()), (1, served));
+        assert_eq!(game.players["Ann"].undo_count, 1);
+
+        game.re_roll_dice(&[]).unwrap();
+        assert!(matches!(game.undo(&[], "Ann"), Err(GameError::UndoNotPossible(_))));
+    }
+
+    #[test]
+    fn undo_of_a_booking_is_possible_until_the_next_player_acts() {
+        let options = GameOptions { undo_policy: UndoPolicy::Limited, undo_limit: 2, ..GameOptions::default() };
+        let mut game = scripted_game(&["Ann", "Bob"], vec![1, 2, 3, 4, 6], options);
+        game.book_dice_roll(BookingType::SmallStraight, None).unwrap();
+        game.undo(&[], "Ann").unwrap();
+        assert_eq!(game.current_player, "Ann");
+        assert_eq!(game.players["Ann"].score, 0);
+        assert_eq!(game.dice_rolls, vec![1, 2, 3, 4, 6]);
+
+        game.book_dice_roll(BookingType::Chance, None).unwrap();
+        game.re_roll_dice(&[]).unwrap();
+        assert!(matches!(game.undo(&[], "Ann"), Err(GameError::UndoNotPossible(_))));
+    }
+
+    #[test]
+    fn consent_undo_needs_every_other_human() {
+        let mut players = humans(&["Ann", "Bob", "Cid"]);
+        players.push(KniffelPlayer::bot(&"Bot".to_string(), BotStrategy::Greedy));
+        let mut game = KniffelGame::new(players, GameOptions::default(), Box::new(ScriptedDice::new(vec![3, 1]))).unwrap();
+        game.re_roll_dice(&[]).unwrap();
+
+        game.undo(&[], "Ann").unwrap();
+        assert_eq!(game.missing_undo_approvals(), vec!["Bob", "Cid"]);
+        assert!(matches!(game.undo(&[], "Ann"), Err(GameError::UndoNotPossible(_))));
+        game.undo(&[], "Bob").unwrap();
+        assert_eq!(game.roll_round, 2);
+        game.undo(&[], "Cid").unwrap();
+        assert_eq!(game.roll_round, 1);
+        assert!(game.pending_undo.is_none());
+        assert_eq!(game.players["Ann"].undo_count, 1);
+    }
+
+    #[test]
+    fn rebuilding_from_the_events_reproduces_a_finished_game() {
+        let players = vec![
+            KniffelPlayer::new(&"Ann".to_string()),
+            KniffelPlayer::bot(&"Bot".to_string(), BotStrategy::Heuristic),
+        ];
+        let mut game = KniffelGame::new(players, GameOptions::default(), Box::new(SeededDice::new(7))).unwrap();
+        play(&mut game, 1000);
+        assert_eq!(game.state, GameState::Ended);
+
+        let rebuilt = KniffelGame::from_events(&game.game_id, &game.events).unwrap();
+        assert_same_game(&rebuilt, &game);
+        assert_eq!(rebuilt.turns.len(), 2 * KNIFFEL.categories().len());
+    }
+
+    #[test]
+    fn rebuilt_game_rolls_the_same_dice_as_the_original() {
+        let options = GameOptions { rule_set: &YAHTZEE, ..GameOptions::default() };
+        let mut game = KniffelGame::new(humans(&["Ann", "Bob"]), options, Box::new(SeededDice::new(3))).unwrap();
+        play(&mut game, 20);
+
+        let mut rebuilt = KniffelGame::from_events(&game.game_id, &game.events).unwrap();
+        assert_same_game(&rebuilt, &game);
+        for _ in 0..3 {
+            if game.can_roll() {
+                game.re_roll_dice(&[]).unwrap();
+                rebuilt.re_roll_dice(&[]).unwrap();
+            } else {
+                let (booking_type, column, _) = game.booking_options()[0];
+                game.book_dice_roll(booking_type, Some(column)).unwrap();
+                rebuilt.book_dice_roll(booking_type, Some(column)).unwrap();
+            }
+            assert_same_game(&rebuilt, &game);
         }
     }
+
+    #[test]
+    fn every_prefix_of_the_log_replays_to_a_step_of_the_game() {
+        let mut game = scripted_game(&["Ann", "Bob"], vec![1, 2, 3, 4, 6, 5], GameOptions::default());
+        game.re_roll_dice(&[1, 2, 3, 4]).unwrap();
+        game.book_dice_roll(BookingType::LargeStraight, None).unwrap();
+        game.book_dice_roll(BookingType::Chance, None).unwrap();
+
+        for step in 0..game.events.len() {
+            KniffelGame::from_events(&game.game_id, &game.events[..=step]).unwrap();
+        }
+        let booked = game.events.iter()
+            .position(|event| matches!(event.kind, GameEventKind::CategoryBooked { .. }))
+            .unwrap();
+        let before = KniffelGame::from_events(&game.game_id, &game.events[..booked]).unwrap();
+        let after = KniffelGame::from_events(&game.game_id, &game.events[..=booked]).unwrap();
+        assert_eq!((before.players["Ann"].score, before.roll_round), (0, 2));
+        assert_eq!(after.players["Ann"].score, 40);
+        assert_same_game(&KniffelGame::from_events(&game.game_id, &game.events).unwrap(), &game);
+    }
+
+    #[test]
+    fn undone_moves_are_left_out_when_the_events_are_folded() {
+        let options = GameOptions { undo_policy: UndoPolicy::Limited, undo_limit: 1, ..GameOptions::default() };
+        let mut game = scripted_game(&["Ann", "Bob"], vec![1, 2, 3, 4, 6], options);
+        game.book_dice_roll(BookingType::SmallStraight, None).unwrap();
+        game.undo(&[], "Ann").unwrap();
+
+        let effective: Vec<&str> = effective_events(&game.events).iter().map(|event| event.kind.name()).collect();
+        assert_eq!(effective, vec!["GAME_CREATED", "DICE_ROLLED", "ACTION_UNDONE"]);
+        assert_same_game(&KniffelGame::from_events(&game.game_id, &game.events).unwrap(), &game);
+    }
+
+    #[test]
+    fn inconsistent_logs_are_invalid() {
+        let game = scripted_game(&["Ann", "Bob"], vec![2], GameOptions::default());
+        assert!(matches!(KniffelGame::from_events(&game.game_id, &game.events[1..]), Err(GameError::EventLogInvalid(_))));
+
+        let mut events = game.events.clone();
+        events.push(GameEvent {
+            sequence: 2,
+            timestamp: 0,
+            kind: GameEventKind::DiceRolled { player_name: "Eve".to_string(), kept_dice: vec![], dice: vec![1, 1, 1, 1, 1] },
+        });
+        assert!(matches!(KniffelGame::from_events(&game.game_id, &events), Err(GameError::EventLogInvalid(_))));
+
+        events[2].kind = GameEventKind::DiceRolled { player_name: "Bob".to_string(), kept_dice: vec![], dice: vec![1, 1, 1, 1, 1] };
+        assert!(matches!(KniffelGame::from_events(&game.game_id, &events), Err(GameError::EventLogInvalid(_))));
+    }
 }
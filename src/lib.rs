@@ -0,0 +1,9 @@
+//! Game engine of the Kniffel REST API: rules, scoring, the game state machine, the solver and bots.
+//! It does not depend on the database, see the server binary for persistence and the HTTP API.
+pub mod analysis;
+pub mod bot;
+pub mod game_error;
+pub mod game_logic;
+pub mod rule_set;
+pub mod scoring;
+pub mod solver;
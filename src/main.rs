@@ -1,38 +1,166 @@
 #[macro_use]
 extern crate rocket;
-mod game_logic;
-mod scoring;
+mod auth;
 mod data_persistence;
 mod models;
+mod notifier;
 mod schema;
 
 
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::{json::Json, Deserialize, Serialize};
-use std::collections::HashSet;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Shutdown, State};
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::str::FromStr;
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa::{OpenApi, ToSchema};
-use game_logic::BookingType;
 use data_persistence::persist_new_game;
 use data_persistence::load_game_from_persistent_store;
 use data_persistence::update_game_to_persistent_store;
+use data_persistence::load_turns_from_persistent_store;
+use data_persistence::load_events_from_persistent_store;
+use data_persistence::load_tokens_from_persistent_store;
+use data_persistence::rebuild_game_in_persistent_store;
+use crate::auth::{GameToken, GameTokens};
 use crate::data_persistence::init;
-use crate::game_logic::{KniffelGame, KniffelPlayer};
+use crate::notifier::{notifier_from_env, GameNotifier};
+use kniffel::analysis::{analyze, DecisionReview, GameAnalysis, PlayerReview};
+use kniffel::bot::BotStrategy;
+use kniffel::game_error::{GameError, ProblemResponse};
+use kniffel::game_logic::{BookingType, ColumnOrder, GameEvent, GameEventKind, GameOptions, GameState, KniffelGame, KniffelPlayer, PendingUndo, ScoreColumn, SeededDice, Turn, UndoPolicy};
+use kniffel::rule_set::{rule_set_by_name, RuleSet, KNIFFEL};
+use kniffel::solver::{ready_solver_for, supports, warm_up, Alternative, Decision};
 
 #[derive(Deserialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 #[serde(rename_all = "camelCase")]
 struct CreateGameRequest {
+    /// human players; every name of the game, bots included, must be unique and not empty
     player_names: Vec<String>,
+    /// computer-controlled players, seated after the human players
+    #[serde(default)]
+    bots: Vec<BotSeat>,
+    /// KNIFFEL (default), YAHTZEE, YATZY, GENERALA or MAXI_YATZY
+    #[serde(default)]
+    rule_set: Option<String>,
+    /// seat the players in random order instead of the order of `player_names`
+    #[serde(default)]
+    shuffle_seats: bool,
+    /// seed for the dice, a random seed is used if missing
+    #[serde(default)]
+    seed: Option<u64>,
+    /// scorecard columns per player, 3 for Triple Kniffel; column i counts i + 1 times
+    #[serde(default)]
+    column_count: Option<usize>,
+    /// order of each column: FREE (default), DOWN, UP or ANNOUNCED; sets the number of columns if given
+    #[serde(default)]
+    column_orders: Option<Vec<String>>,
+    /// CONSENT (default): an undo needs the approval of the other human players;
+    /// LIMITED: every player may undo `undoLimit` times without approval
+    #[serde(default)]
+    undo_policy: Option<String>,
+    /// undos per player for the LIMITED undo policy, 1 if missing
+    #[serde(default)]
+    undo_limit: Option<i32>,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "camelCase")]
+struct BotSeat {
+    name: String,
+    /// RANDOM, GREEDY, HEURISTIC or OPTIMAL
+    strategy: String,
+}
+
+impl CreateGameRequest {
+    /// Human players followed by the bots.
+    fn players(&self) -> Result<Vec<KniffelPlayer>, GameError> {
+        let mut players: Vec<KniffelPlayer> = self.player_names
+            .iter()
+            .map(KniffelPlayer::new)
+            .collect();
+        for bot_seat in &self.bots {
+            let strategy = BotStrategy::from_str(&bot_seat.strategy)
+                .map_err(|_| GameError::InvalidGameOptions(format!("Unknown bot strategy {}", bot_seat.strategy)))?;
+            players.push(KniffelPlayer::bot(&bot_seat.name, strategy));
+        }
+        Ok(players)
+    }
+
+    /// The order of every scorecard column requested.
+    fn columns(&self) -> Result<Vec<ColumnOrder>, GameError> {
+        match (&self.column_orders, self.column_count) {
+            (None, column_count) => Ok(vec![ColumnOrder::Free; column_count.unwrap_or(1)]),
+            (Some(orders), Some(column_count)) if orders.len() != column_count => Err(GameError::InvalidGameOptions(
+                "columnCount does not match the number of columnOrders".to_string())),
+            (Some(orders), _) => orders.iter()
+                .map(|order| ColumnOrder::from_str(order)
+                    .map_err(|_| GameError::InvalidGameOptions(format!("Unknown column order {}", order))))
+                .collect(),
+        }
+    }
+
+    fn undo_policy(&self) -> Result<UndoPolicy, GameError> {
+        match &self.undo_policy {
+            None => Ok(UndoPolicy::Consent),
+            Some(policy) => UndoPolicy::from_str(policy)
+                .map_err(|_| GameError::InvalidGameOptions(format!("Unknown undo policy {}", policy))),
+        }
+    }
+}
+
+
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "camelCase")]
+struct ProbabilityRequest {
+    /// values of the dice to keep, the other dice are thrown again
+    #[serde(default)]
+    dice_to_keep: Vec<i32>,
+    /// throws left in the turn, defaults to the throws the current player has left
+    #[serde(default)]
+    rolls_left: Option<i32>,
+}
 
 #[derive(Deserialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 #[serde(rename_all = "camelCase")]
 struct DiceRollRequest {
+    /// values of the dice to keep, the dice are sorted after the roll
+    #[serde(default)]
     dice_to_keep: Vec<i32>,
+    /// hold dice by position: one flag per die, dice stay in place
+    #[serde(default)]
+    hold_mask: Option<Vec<bool>>,
+    /// hold dice by position: indices of the dice to keep, dice stay in place
+    #[serde(default)]
+    hold_positions: Option<Vec<usize>>,
+}
+
+impl DiceRollRequest {
+    /// The positions to keep if the request holds dice by position, `None` for the value-based mode.
+    /// Only one of `diceToKeep`, `holdMask` and `holdPositions` may be given.
+    fn positions_to_keep(&self, dice_count: usize) -> Result<Option<Vec<usize>>, GameError> {
+        let modes = [!self.dice_to_keep.is_empty(), self.hold_mask.is_some(), self.hold_positions.is_some()];
+        if modes.iter().filter(|&&given| given).count() > 1 {
+            return Err(GameError::InvalidHoldRequest(
+                "specify only one of diceToKeep, holdMask and holdPositions".to_string()));
+        }
+        match (&self.hold_mask, &self.hold_positions) {
+            (Some(mask), _) if mask.len() != dice_count => Err(GameError::InvalidHoldRequest(
+                format!("holdMask must have {} entries", dice_count))),
+            (Some(mask), _) => Ok(Some(mask.iter()
+                .enumerate()
+                .filter(|(_, &hold)| hold)
+                .map(|(position, _)| position)
+                .collect())),
+            (None, positions) => Ok(positions.clone()),
+        }
+    }
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -40,156 +168,994 @@ struct DiceRollRequest {
 #[serde(rename_all = "camelCase")]
 struct BookRollRequest {
     booking_type: String,
+    /// index of the scorecard column, the first column where `bookingType` may be booked if missing
+    #[serde(default)]
+    column: Option<usize>,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "camelCase")]
+struct AnnounceRequest {
+    booking_type: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "camelCase")]
+struct UndoRequest {
+    /// player asking for the undo of their last move, or approving the pending undo
+    player_name: String,
+}
+
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ColumnData {
+    multiplier: i32,
+    /// FREE, DOWN, UP or ANNOUNCED
+    order: String,
+    /// unweighted total of the column
+    score: i32,
+    upper_score: i32,
+    bonus: i32,
+    lower_score: i32,
+    kniffel_bonus_count: i32,
+    used_booking_types: Vec<String>,
+    available_booking_types: Vec<String>,
+    /// points the column would gain per available booking type with the dice on the table, current player only
+    potential_scores: BTreeMap<String, i32>,
+}
+
+impl ColumnData {
+    /// `available` are the booking types that may be booked into the column next.
+    pub fn new(column: &ScoreColumn, available: &[BookingType], potential_scores: BTreeMap<String, i32>,
+               rule_set: &dyn RuleSet) -> Self {
+        let used = rule_set.categories()
+            .iter()
+            .filter(|bt| column.has_used_booking_type(bt));
+        ColumnData {
+            multiplier: column.multiplier,
+            order: column.order.to_string(),
+            score: column.total(),
+            upper_score: column.upper_score,
+            bonus: column.bonus,
+            lower_score: column.lower_score,
+            kniffel_bonus_count: column.kniffel_bonus_count,
+            used_booking_types: used.map(|bt| bt.to_string()).collect(),
+            available_booking_types: available.iter().map(|bt| bt.to_string()).collect(),
+            potential_scores,
+        }
+    }
+}
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct PlayerData {
     name: String,
     score: i32,
+    /// section scores over all columns, each times its multiplier
+    upper_score: i32,
+    bonus: i32,
+    lower_score: i32,
+    kniffel_bonus_count: i32,
+    saved_rolls: i32,
+    /// number of hints the player asked for
+    hint_count: i32,
+    /// number of the player's moves that were undone
+    undo_count: i32,
+    /// strategy of a computer-controlled player, missing for humans
+    bot: Option<String>,
+    columns: Vec<ColumnData>,
 }
 
 impl PlayerData {
-    pub fn new(_name: String, _score: i32) -> Self {
+    pub fn new(player: &KniffelPlayer, game: &KniffelGame) -> Self {
+        let is_current_player = player.name == game.current_player;
+        let columns = player.columns.iter()
+            .enumerate()
+            .map(|(index, column)| {
+                if !is_current_player {
+                    let available = column.bookable_types(game.rule_set, None);
+                    return ColumnData::new(column, &available, BTreeMap::new(), game.rule_set);
+                }
+                let available = game.bookable_types(index);
+                let potential_scores = available.iter()
+                    .filter_map(|&bt| Some((bt.to_string(), game.potential_score(bt, index)?)))
+                    .collect();
+                ColumnData::new(column, &available, potential_scores, game.rule_set)
+            })
+            .collect();
         PlayerData {
-            name: _name.to_string(),
-            score: _score,
+            name: player.name.to_string(),
+            score: player.score,
+            upper_score: player.weighted_sum(|column| column.upper_score),
+            bonus: player.weighted_sum(|column| column.bonus),
+            lower_score: player.weighted_sum(|column| column.lower_score),
+            kniffel_bonus_count: player.columns.iter().map(|column| column.kniffel_bonus_count).sum(),
+            saved_rolls: player.saved_rolls,
+            hint_count: player.hint_count,
+            undo_count: player.undo_count,
+            bot: player.bot.map(|strategy| strategy.to_string()),
+            columns,
         }
     }
 }
 
 
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TurnData {
+    player_name: String,
+    /// dice after every throw of the turn
+    rolls: Vec<Vec<i32>>,
+    /// dice kept before every re-roll
+    kept_dice: Vec<Vec<i32>>,
+    booking_type: String,
+    column: usize,
+    /// points the player's score gained
+    points: i32,
+}
+
+impl TurnData {
+    pub fn new(turn: &Turn) -> Self {
+        TurnData {
+            player_name: turn.player_name.to_string(),
+            rolls: turn.rolls.clone(),
+            kept_dice: turn.kept_dice.clone(),
+            booking_type: turn.booking_type.to_string(),
+            column: turn.column,
+            points: turn.points,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct GameEventData {
+    /// position in the game's event log, starting at 0
+    sequence: i32,
+    /// milliseconds since the Unix epoch
+    timestamp: i64,
+    /// GAME_CREATED, DICE_ROLLED, ANNOUNCED, CATEGORY_BOOKED, TURN_PASSED, GAME_ENDED, HINT_GIVEN,
+    /// UNDO_REQUESTED, UNDO_APPROVED or ACTION_UNDONE
+    kind: String,
+    /// player the event is about, none for the creation and the end of the game
+    player_name: Option<String>,
+    /// players in seat order, for the creation of the game
+    players: Option<Vec<String>>,
+    /// rule set of a created game
+    rule_set: Option<String>,
+    /// column orders of a created game
+    column_orders: Option<Vec<String>>,
+    /// dice kept before a roll
+    kept_dice: Option<Vec<i32>>,
+    /// dice on the table after a roll
+    dice: Option<Vec<i32>>,
+    /// category announced or booked
+    booking_type: Option<String>,
+    /// column booked into
+    column: Option<usize>,
+    /// points the player's score gained by a booking
+    points: Option<i32>,
+    /// sequence of the first event taken back by an undo
+    undone_sequence: Option<i32>,
+}
+
+impl GameEventData {
+    pub fn new(event: &GameEvent) -> Self {
+        let mut data = GameEventData {
+            sequence: event.sequence,
+            timestamp: event.timestamp,
+            kind: event.kind.name().to_string(),
+            player_name: event.kind.player_name().map(str::to_string),
+            players: None,
+            rule_set: None,
+            column_orders: None,
+            kept_dice: None,
+            dice: None,
+            booking_type: None,
+            column: None,
+            points: None,
+            undone_sequence: None,
+        };
+        match &event.kind {
+            GameEventKind::GameCreated { players, rule_set, columns, .. } => {
+                data.players = Some(players.iter().map(|player| player.name.to_string()).collect());
+                data.rule_set = Some(rule_set.to_string());
+                data.column_orders = Some(columns.iter().map(|order| order.to_string()).collect());
+            }
+            GameEventKind::DiceRolled { kept_dice, dice, .. } => {
+                data.kept_dice = Some(kept_dice.clone());
+                data.dice = Some(dice.clone());
+            }
+            GameEventKind::Announced { booking_type, .. } => data.booking_type = Some(booking_type.to_string()),
+            GameEventKind::CategoryBooked { booking_type, column, points, .. } => {
+                data.booking_type = Some(booking_type.to_string());
+                data.column = Some(*column);
+                data.points = Some(*points);
+            }
+            GameEventKind::ActionUndone { undone_sequence, .. } => data.undone_sequence = Some(*undone_sequence),
+            GameEventKind::TurnPassed { .. }
+            | GameEventKind::GameEnded
+            | GameEventKind::HintGiven { .. }
+            | GameEventKind::UndoRequested { .. }
+            | GameEventKind::UndoApproved { .. } => {}
+        }
+        data
+    }
+}
+
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct GameResponse {
     game_id: String,
+    rule_set: String,
     player_data: Vec<PlayerData>,
     current_player_name: String,
     state: String,
     used_booking_types: Vec<String>,
     available_booking_types: Vec<String>,
-    dice_rolls: [i32; 5],
+    /// points the current player would gain per booking type with the dice on the table,
+    /// including upper-section bonus, extra Kniffel bonus, joker rules and column multiplier
+    potential_scores: BTreeMap<String, i32>,
+    dice_rolls: Vec<i32>,
     roll_round: i32,
+    can_roll: bool,
+    can_book: bool,
+    can_announce: bool,
+    /// category announced by the current player this turn
+    announcement: Option<String>,
+    rolls_left: i32,
+    /// turns the bots played during this request
+    bot_turns: Vec<TurnData>,
+    /// CONSENT or LIMITED
+    undo_policy: String,
+    /// undos per player for the LIMITED undo policy
+    undo_limit: Option<i32>,
+    /// undo of the last move waiting for approval
+    pending_undo: Option<PendingUndoData>,
+}
+
+/// A new game with its secret tokens, which are only sent once.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CreatedGameResponse {
+    #[serde(flatten)]
+    game: GameResponse,
+    /// token of every human player, to be sent in the `X-Game-Token` header to act for that player
+    seat_tokens: BTreeMap<String, String>,
+    /// token to read the game without playing
+    spectator_token: String,
+}
+
+/// A change of a game, sent to everyone watching it.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct GameUpdateData<'a> {
+    game: &'a GameResponse,
+    /// events of the change, oldest first
+    events: Vec<GameEventData>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct PendingUndoData {
+    requested_by: String,
+    approved_by: Vec<String>,
+    /// human players who still have to approve
+    waiting_for: Vec<String>,
+}
+
+impl PendingUndoData {
+    pub fn new(pending_undo: &PendingUndo, game: &KniffelGame) -> Self {
+        PendingUndoData {
+            requested_by: pending_undo.requested_by.to_string(),
+            approved_by: pending_undo.approved_by.clone(),
+            waiting_for: game.missing_undo_approvals(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct HintOption {
+    /// KEEP or BOOK
+    action: String,
+    /// dice to keep and re-roll the others, for KEEP
+    dice_to_keep: Option<Vec<i32>>,
+    /// category to book, for BOOK
+    booking_type: Option<String>,
+    /// final score expected with optimal play after this decision
+    expected_score: f64,
+}
+
+impl HintOption {
+    pub fn new(alternative: &Alternative) -> Self {
+        let (action, dice_to_keep, booking_type) = match &alternative.decision {
+            Decision::Keep(dice) => ("KEEP", Some(dice.clone()), None),
+            Decision::Book(booking_type) => ("BOOK", None, Some(booking_type.to_string())),
+        };
+        HintOption {
+            action: action.to_string(),
+            dice_to_keep,
+            booking_type,
+            expected_score: alternative.expected_score,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct HintResponse {
+    player_name: String,
+    recommendation: HintOption,
+    /// every possible decision, best first
+    alternatives: Vec<HintOption>,
+    /// number of hints the player asked for, including this one
+    hint_count: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct DecisionData {
+    player_name: String,
+    /// turn of the player, starting at 1
+    turn: usize,
+    /// throw of the turn after which the decision was taken, starting at 1
+    throw: usize,
+    dice: Vec<i32>,
+    /// decision taken, with the final score it was expected to lead to
+    decision: HintOption,
+    best_decision: HintOption,
+    /// expected final points the decision gave away
+    expected_loss: f64,
+    blunder: bool,
+}
+
+impl DecisionData {
+    pub fn new(review: &DecisionReview) -> Self {
+        let option = |decision: &Decision, expected_score: f64| {
+            HintOption::new(&Alternative { decision: decision.clone(), expected_score })
+        };
+        DecisionData {
+            player_name: review.player_name.to_string(),
+            turn: review.turn,
+            throw: review.throw,
+            dice: review.dice.clone(),
+            decision: option(&review.decision, review.expected_score),
+            best_decision: option(&review.best_decision, review.best_expected_score),
+            expected_loss: review.expected_loss(),
+            blunder: review.is_blunder(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct PlayerAnalysisData {
+    player_name: String,
+    decisions: usize,
+    best_decisions: usize,
+    blunders: usize,
+    /// expected final points given away by all decisions
+    expected_loss: f64,
+    /// share of best decisions in percent
+    accuracy: f64,
+}
+
+impl PlayerAnalysisData {
+    pub fn new(review: &PlayerReview) -> Self {
+        PlayerAnalysisData {
+            player_name: review.player_name.to_string(),
+            decisions: review.decisions,
+            best_decisions: review.best_decisions,
+            blunders: review.blunders,
+            expected_loss: review.expected_loss,
+            accuracy: review.accuracy,
+        }
+    }
+}
+
+/// Number of blunders listed in an `AnalysisResponse`.
+const BIGGEST_BLUNDERS: usize = 5;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AnalysisResponse {
+    game_id: String,
+    /// one summary per player, in seat order
+    players: Vec<PlayerAnalysisData>,
+    /// the decisions that gave away the most expected points, biggest first
+    biggest_blunders: Vec<DecisionData>,
+    /// every decision of the game in the order it was taken
+    decisions: Vec<DecisionData>,
+}
+
+impl AnalysisResponse {
+    pub fn new(game_id: &str, analysis: &GameAnalysis) -> Self {
+        AnalysisResponse {
+            game_id: game_id.to_string(),
+            players: analysis.players.iter().map(PlayerAnalysisData::new).collect(),
+            biggest_blunders: analysis.biggest_blunders(BIGGEST_BLUNDERS).into_iter().map(DecisionData::new).collect(),
+            decisions: analysis.decisions.iter().map(DecisionData::new).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ProbabilityResponse {
+    player_name: String,
+    dice_rolls: Vec<i32>,
+    dice_to_keep: Vec<i32>,
+    rolls_left: i32,
+    /// chance to end the turn with the pattern, each pattern played for on its own
+    kniffel: f64,
+    large_straight: f64,
+    small_straight: f64,
+    full_house: f64,
+    four_of_a_kind: f64,
+    three_of_a_kind: f64,
+    /// expected points of each open category when playing for it, without bonuses and multipliers
+    expected_scores: BTreeMap<String, f64>,
 }
 
 #[utoipa::path(
     request_body = CreateGameRequest,
     responses(
-            (status = 200, description = "Create a new game", body = GameResponse)
+            (status = 200, description = "Create a new game, with the secret tokens of the seats and for spectators", body = CreatedGameResponse),
+            (status = 400, description = "Unknown rule set, invalid columns, unknown bot strategy, or player names that are empty, used twice or contain a comma", body = ProblemResponse),
+            (status = 500, description = "The game could not be stored or a bot cannot move", body = ProblemResponse)
     )
 )]
 #[post("/api/v1/game/<_..>", rank = 5, format = "json", data = "<player_request>")]
-fn post_player_names(player_request: Json<CreateGameRequest>) -> Json<GameResponse> {
-    let player_names = &player_request.player_names;
+fn post_player_names(player_request: Json<CreateGameRequest>) -> Result<Json<CreatedGameResponse>, GameError> {
+    let players_vec = player_request.players()?;
 
-    let players_vec: Vec<KniffelPlayer> = player_names
-        .into_iter()
-        .map(KniffelPlayer::new)
-        .collect();
+    let rule_set_name = player_request.rule_set.as_deref().unwrap_or(KNIFFEL.name());
+    let rule_set = rule_set_by_name(rule_set_name)
+        .ok_or_else(|| GameError::UnknownRuleSet(rule_set_name.to_string()))?;
+    let dice = match player_request.seed {
+        Some(seed) => SeededDice::new(seed),
+        None => SeededDice::from_random_seed(),
+    };
+    let options = GameOptions {
+        rule_set,
+        columns: player_request.columns()?,
+        shuffle_seats: player_request.shuffle_seats,
+        undo_policy: player_request.undo_policy()?,
+        undo_limit: player_request.undo_limit.unwrap_or(1),
+    };
+    let kniffel_game = KniffelGame::new(players_vec, options, Box::new(dice))?;
+    let tokens = GameTokens::issue(&kniffel_game);
 
-    let kniffel_game = KniffelGame::new(players_vec);
-    let game_id = kniffel_game.game_id.to_string();
+    persist_new_game(&kniffel_game, &tokens)?;
 
-    persist_new_game(&kniffel_game);
-
-    create_return_data(&game_id).expect("Failed to create return data")
+    Ok(Json(CreatedGameResponse {
+        game: create_return_data(&kniffel_game).into_inner(),
+        seat_tokens: tokens.seats.into_iter().collect(),
+        spectator_token: tokens.spectator,
+    }))
 }
 
 #[utoipa::path(
     responses(
-            (status = 200, description = "Retrieve a game", body = GameResponse)
+            (status = 200, description = "Retrieve a game", body = GameResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
     )
 )]
 #[get("/api/v1/game/<game_id>")]
-fn get_player_names(game_id: String) -> Option<Json<GameResponse>> {
-    create_return_data(&game_id)
+fn get_player_names(game_id: String, token: GameToken) -> Result<Json<GameResponse>, GameError> {
+    let game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&token)?;
+
+    Ok(create_return_data(&game))
 }
 
 #[utoipa::path(
     request_body = DiceRollRequest,
     responses(
-            (status = 200, description = "(Re)-roll the dice", body = GameResponse)
+            (status = 200, description = "(Re)-roll the dice, the player may book or roll again afterwards", body = GameResponse),
+            (status = 400, description = "Invalid dice to keep, or the dice to hold are given in more than one way", body = ProblemResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not the seat token of the current player", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "No throws left this turn or the game has ended", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of the current player"),
     )
 )]
+/// Re-rolls all dice not listed in `diceToKeep`.
+///
+/// Instead of values, dice can be held by position with either `holdMask` (one flag per die) or
+/// `holdPositions` (indices). In that mode the dice are not sorted and every held die stays in place.
+/// Only one of `diceToKeep`, `holdMask` and `holdPositions` may be given.
+///
+/// Every turn starts with an automatic first throw. The player may re-roll up to two more times,
+/// three throws per turn in total, and can book after any of them. In MAXI_YATZY unused throws are
+/// saved for later turns. After the third throw the game
+/// is in state BOOK and only booking is possible.
 #[post("/api/v1/game/<game_id>/roll", format = "json", data = "<dice_roll_request>")]
-fn roll(game_id: String, dice_roll_request: Json<DiceRollRequest>) -> Option<Json<GameResponse>> {
+fn roll(game_id: String, dice_roll_request: Json<DiceRollRequest>, token: GameToken,
+        notifier: &State<Box<dyn GameNotifier>>) -> Result<Json<GameResponse>, GameError> {
     let mut game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_player(&token, &game.current_player)?;
 
-    game.re_roll_dice(&dice_roll_request.dice_to_keep);
+    match dice_roll_request.positions_to_keep(game.dice_rolls.len())? {
+        Some(positions_to_keep) => game.re_roll_dice_at_positions(&positions_to_keep)?,
+        None => game.re_roll_dice(&dice_roll_request.dice_to_keep)?,
+    }
 
-    update_game_to_persistent_store(&game);
+    update_game_to_persistent_store(&game)?;
 
-    create_return_data(&game_id)
+    let response = create_return_data(&game);
+    notify(notifier, &game, &response);
+    Ok(response)
 }
 
 #[utoipa::path(
     request_body = BookRollRequest,
     responses(
-            (status = 200, description = "Book a dice roll to score", body = GameResponse)
+            (status = 200, description = "Book a dice roll to score and pass the turn to the next player", body = GameResponse),
+            (status = 400, description = "Unknown booking type or column", body = ProblemResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not the seat token of the current player", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "Booking type already used, out of order or not allowed, or the game has ended", body = ProblemResponse),
+            (status = 500, description = "A bot cannot move, the booking is not stored", body = ProblemResponse)
     ),
     params(
         ("game_id" = String, Path, description = "Game id to score"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of the current player"),
     )
 )]
+/// Books the dice on the table into `bookingType` of the scorecard column `column`.
+///
+/// Booking is allowed after the first, second or third throw of a turn. Afterwards the next player's
+/// turn starts with their first throw, or the game ends once all categories of all columns are booked.
+/// Turns of bots are played right away until a human is to move.
+///
+/// DOWN columns are filled top-down and UP columns bottom-up. ANNOUNCED columns take the category announced
+/// after the first throw; without an announcement a category there can only be crossed out with 0 points.
 #[post("/api/v1/game/<game_id>/book", format = "json", data = "<dice_book_request>")]
-fn book(game_id: String, dice_book_request: Json<BookRollRequest>) -> Option<Json<GameResponse>> {
+fn book(game_id: String, dice_book_request: Json<BookRollRequest>, token: GameToken,
+        notifier: &State<Box<dyn GameNotifier>>) -> Result<Json<GameResponse>, GameError> {
     let mut game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_player(&token, &game.current_player)?;
 
-    game.book_dice_roll(BookingType::from_str(&dice_book_request.booking_type.to_string()).unwrap());
-
-    update_game_to_persistent_store(&game);
-
-    create_return_data(&game_id)
-}
-
-fn create_return_data(game_id: &String) -> Option<Json<GameResponse>> {
-    if let Some(game) = load_game_from_persistent_store(game_id) {
-        // Define the full set of BookingType
-        let full_set: HashSet<BookingType> = [
-            BookingType::Ones,
-            BookingType::Twos,
-            BookingType::Threes,
-            BookingType::Fours,
-            BookingType::Fives,
-            BookingType::Sixes,
-            BookingType::ThreeOfAKind,
-            BookingType::FourOfAKind,
-            BookingType::FullHouse,
-            BookingType::SmallStraight,
-            BookingType::LargeStraight,
-            BookingType::Kniffel,
-            BookingType::Chance,
-        ]
-            .iter()
-            .cloned()
-            .collect();
+    let booking_type = BookingType::from_str(&dice_book_request.booking_type)
+        .map_err(|_| GameError::UnknownCategory(dice_book_request.booking_type.to_string()))?;
+    game.book_dice_roll(booking_type, dice_book_request.column)?;
 
-        let player = game.players.get(&game.current_player);
+    update_game_to_persistent_store(&game)?;
 
-        let inverted_set: HashSet<BookingType> = full_set
-            .difference(&player.unwrap().used_booking_types)
-            .cloned()
-            .collect();
+    let response = create_return_data(&game);
+    notify(notifier, &game, &response);
+    Ok(response)
+}
 
-        let mut player_data: Vec<PlayerData> = game.players.values()
-            .map(|e| PlayerData::new(e.name.clone(), e.score))
-            .collect();
-        player_data.sort_by(|a, b| a.name.cmp(&b.name));
+#[utoipa::path(
+    request_body = AnnounceRequest,
+    responses(
+            (status = 200, description = "Announce the category to book into an announced column this turn", body = GameResponse),
+            (status = 400, description = "Unknown booking type", body = ProblemResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not the seat token of the current player", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "Not right after the first throw, already announced or booking type already used", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id to announce in"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of the current player"),
+    )
+)]
+/// Announces `bookingType` for an ANNOUNCED column.
+///
+/// Only possible right after the first throw of a turn. The turn must then end with booking the announced
+/// category into an ANNOUNCED column.
+#[post("/api/v1/game/<game_id>/announce", format = "json", data = "<announce_request>")]
+fn announce(game_id: String, announce_request: Json<AnnounceRequest>, token: GameToken,
+            notifier: &State<Box<dyn GameNotifier>>) -> Result<Json<GameResponse>, GameError> {
+    let mut game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_player(&token, &game.current_player)?;
+
+    let booking_type = BookingType::from_str(&announce_request.booking_type)
+        .map_err(|_| GameError::UnknownCategory(announce_request.booking_type.to_string()))?;
+    game.announce(booking_type)?;
+
+    update_game_to_persistent_store(&game)?;
+
+    let response = create_return_data(&game);
+    notify(notifier, &game, &response);
+    Ok(response)
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "Recommended decision for the current player", body = HintResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not the seat token of the current player", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "No hint for this rule set, for several or restricted columns, or the game has ended", body = ProblemResponse),
+            (status = 503, description = "The solver is still computing its value table after the server started", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id to get a hint for"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of the current player"),
+    )
+)]
+/// Recommends which dice to keep or which category to book for the current player.
+///
+/// The recommendation maximizes the expected final score of the player's scorecard. Every alternative is listed
+/// with the final score it is expected to lead to. Each hint is counted for the player.
+#[get("/api/v1/game/<game_id>/hint")]
+fn hint(game_id: String, token: GameToken, notifier: &State<Box<dyn GameNotifier>>) -> Result<Json<HintResponse>, GameError> {
+    let mut game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_player(&token, &game.current_player)?;
+
+    if !supports(game.rule_set) {
+        return Err(GameError::HintNotAvailable(format!("rule set {} is not supported", game.rule_set.name())));
+    }
+    let solver = ready_solver_for(game.rule_set)
+        .ok_or_else(|| GameError::SolverNotReady(game.rule_set.name().to_string()))?;
+    let position = solver.position(&game)
+        .ok_or_else(|| GameError::HintNotAvailable("only for a single free column while the game is running".to_string()))?;
+    let alternatives = solver.alternatives(&position);
+    let recommendation = alternatives.first()
+        .map(HintOption::new)
+        .ok_or_else(|| GameError::HintNotAvailable("no decision possible".to_string()))?;
+
+    game.record_hint()?;
+    update_game_to_persistent_store(&game)?;
+    notify(notifier, &game, &create_return_data(&game));
+
+    Ok(Json(HintResponse {
+        player_name: game.current_player.to_string(),
+        recommendation,
+        alternatives: alternatives.iter().map(HintOption::new).collect(),
+        hint_count: game.players.get(&game.current_player).map_or(0, |player| player.hint_count),
+    }))
+}
+
+#[utoipa::path(
+    request_body = ProbabilityRequest,
+    responses(
+            (status = 200, description = "Chances of the current player's turn", body = ProbabilityResponse),
+            (status = 400, description = "Dice to keep are not on the table or rolls left are out of range", body = ProblemResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "The game has ended", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
+    )
+)]
+/// Calculates the chances of the current player to end the turn with each dice pattern and the expected points
+/// of their open categories, if they keep `diceToKeep` and throw the other dice.
+///
+/// The chances are exact, every possible throw is enumerated. Nothing changes in the game.
+#[post("/api/v1/game/<game_id>/probabilities", format = "json", data = "<probability_request>")]
+fn probabilities(game_id: String, probability_request: Json<ProbabilityRequest>,
+                 token: GameToken) -> Result<Json<ProbabilityResponse>, GameError> {
+    let game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&token)?;
+
+    let (patterns, expected_scores) = game.turn_odds(&probability_request.dice_to_keep, probability_request.rolls_left)?;
+
+    Ok(Json(ProbabilityResponse {
+        player_name: game.current_player.to_string(),
+        dice_rolls: game.dice_rolls.clone(),
+        dice_to_keep: probability_request.dice_to_keep.clone(),
+        rolls_left: probability_request.rolls_left.unwrap_or_else(|| game.rolls_left()),
+        kniffel: patterns.kniffel,
+        large_straight: patterns.large_straight,
+        small_straight: patterns.small_straight,
+        full_house: patterns.full_house,
+        four_of_a_kind: patterns.four_of_a_kind,
+        three_of_a_kind: patterns.three_of_a_kind,
+        expected_scores: expected_scores.iter()
+            .map(|(booking_type, expected_score)| (booking_type.to_string(), *expected_score))
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "Every decision of the game compared with the best one", body = AnalysisResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "The game has not ended, its rule set or columns are not supported or its turns were not recorded", body = ProblemResponse),
+            (status = 503, description = "The solver is still computing its value table after the server started", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
+    )
+)]
+/// Replays a finished game and compares every keep and book decision with the one maximizing the expected final
+/// score of the player's scorecard.
+///
+/// Reports the expected points each decision gave away, flags blunders of 5 or more points and sums up the
+/// accuracy of every player.
+#[get("/api/v1/game/<game_id>/analysis")]
+fn analysis(game_id: String, token: GameToken) -> Result<Json<AnalysisResponse>, GameError> {
+    let game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&token)?;
+    let turns = load_turns_from_persistent_store(&game_id)?;
+
+    let analysis = analyze(&game, &turns)?;
+
+    Ok(Json(AnalysisResponse::new(&game_id, &analysis)))
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "Every event of the game from its creation on, oldest first", body = [GameEventData]),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
+    )
+)]
+/// The append-only event log of a game, ordered by sequence number.
+#[get("/api/v1/game/<game_id>/events")]
+fn get_events(game_id: String, token: GameToken) -> Result<Json<Vec<GameEventData>>, GameError> {
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&token)?;
+    let events = load_events_from_persistent_store(&game_id)?;
+
+    Ok(Json(events.iter().map(GameEventData::new).collect()))
+}
+
+#[utoipa::path(
+    request_body = UndoRequest,
+    responses(
+            (status = 200, description = "Move taken back, or undo requested or approved and waiting for further approvals", body = GameResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not the seat token of the player in the request", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "Nothing to undo, the next player has already acted, no undos left or already approved", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of the player in the request"),
+    )
+)]
+/// Takes back the last roll or booking as long as the next player has not acted yet.
+///
+/// With the CONSENT undo policy the player who made the move asks for the undo and every other human player
+/// approves it with their own call; the move is taken back with the last approval. With the LIMITED undo policy
+/// the move is taken back right away while the player has undos left. Every request, approval and undo is
+/// recorded in the game's event log.
+#[post("/api/v1/game/<game_id>/undo", format = "json", data = "<undo_request>")]
+fn undo(game_id: String, undo_request: Json<UndoRequest>, token: GameToken,
+        notifier: &State<Box<dyn GameNotifier>>) -> Result<Json<GameResponse>, GameError> {
+    load_tokens_from_persistent_store(&game_id)?.authorize_player(&token, &undo_request.player_name)?;
+    let events = load_events_from_persistent_store(&game_id)?;
+    let mut game = KniffelGame::from_events(&game_id, &events)?;
+
+    game.undo(&events, &undo_request.player_name)?;
+
+    rebuild_game_in_persistent_store(&game)?;
 
-        Some(Json(GameResponse {
-            game_id: game.game_id,
-            player_data,
-            current_player_name: game.current_player,
-            state: game.state.to_string().to_uppercase(),
-            used_booking_types: player.unwrap().used_booking_types.iter().map(|bt| bt.to_string()).collect(),
-            available_booking_types: inverted_set.iter().map(|bt| bt.to_string()).collect(),
-            dice_rolls: game.dice_rolls,
-            roll_round: game.roll_round,
-        }))
-    } else {
-        None
+    // the turns of the rebuilt game were all played before this request
+    game.turns.clear();
+    let response = create_return_data(&game);
+    notify(notifier, &game, &response);
+    Ok(response)
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "The game as it was after the event", body = GameResponse),
+            (status = 400, description = "No event with this sequence number", body = ProblemResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 409, description = "The game has not ended", body = ProblemResponse),
+            (status = 500, description = "The event log does not lead to a game", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
+        ("step" = i32, Query, description = "Sequence number of the event, 0 is the creation of the game"),
+    )
+)]
+/// The game as it was right after the event `step` of its log, to play back a game step by step.
+///
+/// The state is rebuilt by applying the events up to `step` in order, moves undone later are still part of the
+/// game at the steps before their undo. The number of steps is the number of events, see the events endpoint.
+/// Only games that have ended can be played back, a running game is followed with its updates.
+#[get("/api/v1/game/<game_id>/replay?<step>")]
+fn replay(game_id: String, step: i32, token: GameToken) -> Result<Json<GameResponse>, GameError> {
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&token)?;
+    if load_game_from_persistent_store(&game_id)?.state != GameState::Ended {
+        return Err(GameError::ReplayNotAvailable("the game has not ended".to_string()));
     }
+    let events = load_events_from_persistent_store(&game_id)?;
+    if !events.iter().any(|event| event.sequence == step) {
+        return Err(GameError::InvalidReplayStep(step));
+    }
+    let replayed_events = events.partition_point(|event| event.sequence <= step);
+    let mut game = KniffelGame::from_events(&game_id, &events[..replayed_events])?;
+
+    // no bot played during this request
+    game.turns.clear();
+    Ok(create_return_data(&game))
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "Game rebuilt from its event log", body = GameResponse),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not the seat token of a player of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse),
+            (status = 500, description = "The event log does not lead to a game", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player of the game"),
+    )
+)]
+/// Rebuilds the stored state of a game by applying its event log in order and returns the rebuilt game.
+/// Bookings are scored by the current rules, which repairs scores stored by rules that have been fixed since.
+#[post("/api/v1/game/<game_id>/rebuild")]
+fn rebuild(game_id: String, token: GameToken, notifier: &State<Box<dyn GameNotifier>>) -> Result<Json<GameResponse>, GameError> {
+    load_tokens_from_persistent_store(&game_id)?.authorize_any_player(&token)?;
+    let events = load_events_from_persistent_store(&game_id)?;
+    let mut game = KniffelGame::from_events(&game_id, &events)?;
+    rebuild_game_in_persistent_store(&game)?;
+
+    // every turn of the game is in the rebuilt state, none of them was played by a bot during this request
+    game.turns.clear();
+    let response = create_return_data(&game);
+    notify(notifier, &game, &response);
+    Ok(response)
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "All turns the bots of a game played, in order", body = [TurnData]),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
+    )
+)]
+#[get("/api/v1/game/<game_id>/bot-turns")]
+fn get_bot_turns(game_id: String, token: GameToken) -> Result<Json<Vec<TurnData>>, GameError> {
+    let game = load_game_from_persistent_store(&game_id)?;
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&token)?;
+    let turns = load_turns_from_persistent_store(&game_id)?;
+
+    Ok(Json(turns.iter()
+        .filter(|turn| game.is_bot(&turn.player_name))
+        .map(TurnData::new)
+        .collect()))
+}
+
+#[utoipa::path(
+    responses(
+            (status = 200, description = "Server-sent `update` events, one per change of the game", body = GameUpdateData, content_type = "text/event-stream"),
+            (status = 401, description = "Token missing", body = ProblemResponse),
+            (status = 403, description = "Not a token of the game", body = ProblemResponse),
+            (status = 404, description = "Unknown game", body = ProblemResponse)
+    ),
+    params(
+        ("game_id" = String, Path, description = "Game id"),
+        ("X-Game-Token" = Option<String>, Header, description = "Seat token of any player or the spectator token of the game"),
+        ("token" = Option<String>, Query, description = "Token of the game if the header cannot be sent"),
+    )
+)]
+/// Streams every change of a game as server-sent events, so clients do not have to poll the game.
+///
+/// The first `update` is the game as it is now. Every roll, announcement, booking, hint, undo and rebuild is
+/// followed by an `update` with the game as returned to the player who made the change and the events of
+/// that change. Clients that fall too far behind miss updates, the next one carries the whole game again.
+///
+/// Browsers' event sources cannot send headers, so the token may be given as the query parameter `token` instead.
+#[get("/api/v1/game/<game_id>/updates?<token>")]
+fn updates(game_id: String, token: Option<String>, header_token: GameToken, notifier: &State<Box<dyn GameNotifier>>,
+           mut shutdown: Shutdown) -> Result<EventStream![], GameError> {
+    load_tokens_from_persistent_store(&game_id)?.authorize_reader(&header_token.or(token))?;
+    // subscribe before loading the game, so no change in between is missed
+    let mut receiver = notifier.subscribe(&game_id);
+    let game = load_game_from_persistent_store(&game_id)?;
+    let current = update_message(&game, &create_return_data(&game));
+
+    Ok(EventStream! {
+        yield Event::data(current).event("update");
+        loop {
+            let message = select! {
+                message = receiver.recv() => match message {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            yield Event::data(message).event("update");
+        }
+    })
+}
+
+/// Pushes a change of `game` to everyone watching it, `response` is what the request returns.
+fn notify(notifier: &State<Box<dyn GameNotifier>>, game: &KniffelGame, response: &GameResponse) {
+    notifier.publish(&game.game_id, update_message(game, response));
+}
+
+/// The game and the events emitted by the current request, as JSON.
+fn update_message(game: &KniffelGame, response: &GameResponse) -> String {
+    let update = GameUpdateData { game: response, events: game.events.iter().map(GameEventData::new).collect() };
+    serde_json::to_string(&update).expect("game updates can always be serialized")
+}
+
+fn create_return_data(game: &KniffelGame) -> Json<GameResponse> {
+    // a booking type counts as used once it is booked in every column
+    let used_booking_types: Vec<&BookingType> = game.rule_set.categories()
+        .iter()
+        .filter(|bt| game.players.get(&game.current_player)
+            .is_some_and(|player| player.has_used_booking_type(bt)))
+        .collect();
+
+    // a booking type is available if it may be booked into any column now
+    let bookable_types: Vec<BookingType> = (0..game.column_count)
+        .flat_map(|column| game.bookable_types(column))
+        .collect();
+    let available_booking_types: Vec<&BookingType> = game.rule_set.categories()
+        .iter()
+        .filter(|bt| bookable_types.contains(bt))
+        .collect();
+
+    let player_data: Vec<PlayerData> = game.seated_players()
+        .into_iter()
+        .map(|player| PlayerData::new(player, game))
+        .collect();
+
+    Json(GameResponse {
+        game_id: game.game_id.to_string(),
+        rule_set: game.rule_set.name().to_string(),
+        player_data,
+        current_player_name: game.current_player.to_string(),
+        state: game.state.to_string().to_uppercase(),
+        used_booking_types: used_booking_types.iter().map(|bt| bt.to_string()).collect(),
+        available_booking_types: available_booking_types.iter().map(|bt| bt.to_string()).collect(),
+        potential_scores: game.potential_scores()
+            .into_iter()
+            .map(|(bt, points)| (bt.to_string(), points))
+            .collect(),
+        dice_rolls: game.dice_rolls.clone(),
+        roll_round: game.roll_round,
+        can_roll: game.can_roll(),
+        can_book: game.can_book(),
+        can_announce: game.can_announce(),
+        announcement: game.announcement.map(|bt| bt.to_string()),
+        rolls_left: game.rolls_left(),
+        bot_turns: game.turns.iter()
+            .filter(|turn| game.is_bot(&turn.player_name))
+            .map(TurnData::new)
+            .collect(),
+        undo_policy: game.undo_policy.to_string(),
+        undo_limit: Some(game.undo_limit).filter(|_| game.undo_policy == UndoPolicy::Limited),
+        pending_undo: game.pending_undo.as_ref().map(|pending_undo| PendingUndoData::new(pending_undo, game)),
+    })
 }
 
 
@@ -197,7 +1163,10 @@ fn create_return_data(game_id: &String) -> Option<Json<GameResponse>> {
 async fn main() -> Result<(), Box<dyn Error>> {
 
     init();
+    // value tables of the solver take a while to compute, requests get SOLVER_NOT_READY until they are ready
+    rocket::tokio::task::spawn_blocking(warm_up);
 
+    let notifier = notifier_from_env()?;
     let cors = rocket_cors::CorsOptions { ..Default::default() }.to_cors()?;
 
     #[derive(OpenApi)]
@@ -206,10 +1175,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
             post_player_names,
             get_player_names,
             roll,
-            book
+            book,
+            announce,
+            hint,
+            probabilities,
+            analysis,
+            get_bot_turns,
+            get_events,
+            rebuild,
+            undo,
+            replay,
+            updates
         ),
         components(
-            schemas(GameResponse, CreateGameRequest, DiceRollRequest, BookRollRequest, PlayerData)
+            schemas(GameResponse, CreateGameRequest, DiceRollRequest, BookRollRequest, AnnounceRequest, PlayerData, ColumnData, HintResponse, HintOption, ProbabilityRequest, ProbabilityResponse, AnalysisResponse, PlayerAnalysisData, DecisionData, BotSeat, TurnData, GameEventData, UndoRequest, PendingUndoData, GameUpdateData, CreatedGameResponse, ProblemResponse)
         ),
     )]
     struct ApiDoc;
@@ -218,7 +1197,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .configure(rocket::Config::figment()
             .merge(("port", 8080))
             .merge(("address", "0.0.0.0")))
-        .mount("/", routes![post_player_names, get_player_names, roll, book])
+        .mount("/", routes![post_player_names, get_player_names, roll, book, announce, hint, probabilities, analysis, get_bot_turns, get_events, rebuild, undo, replay, updates])
+        .manage(notifier)
         .mount(
             "/",
             SwaggerUi::new("/swagger-ui/<_..>").url("/api-docs/openapi.json", ApiDoc::openapi()),
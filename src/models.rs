@@ -10,6 +10,19 @@ pub struct Game {
     pub stage: String,
     pub dice_rolls: String,
     pub current_player: String,
+    pub rule_set: String,
+    pub dice_seed: i64,
+    pub dice_position: i64,
+    pub column_count: i32,
+    pub announcement: String,
+    pub turn_rolls: String,
+    pub turn_kept_dice: String,
+    pub event_count: i32,
+    pub undo_policy: String,
+    pub undo_limit: i32,
+    pub undo_requested_by: String,
+    pub undo_approved_by: String,
+    pub spectator_token: String,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
@@ -20,7 +33,71 @@ pub struct Player {
     pub game_id: i32,
     pub name: String,
     pub score: i32,
+    pub seat: i32,
+    pub saved_rolls: i32,
+    pub hint_count: i32,
+    pub bot: String,
+    pub undo_count: i32,
+    pub token: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::player_columns)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct PlayerColumn {
+    pub id: i32,
+    pub player_id: i32,
+    pub column_index: i32,
+    pub upper_score: i32,
+    pub bonus: i32,
+    pub lower_score: i32,
+    pub scored_kniffel: bool,
+    pub kniffel_bonus_count: i32,
     pub used_booking_types: String,
+    pub column_order: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::turns)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct TurnRecord {
+    pub id: i32,
+    pub game_id: i32,
+    pub player_name: String,
+    pub rolls: String,
+    pub kept_dice: String,
+    pub booking_type: String,
+    pub column_index: i32,
+    pub points: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::game_events)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct GameEventRecord {
+    pub id: i32,
+    pub game_id: i32,
+    pub sequence_number: i32,
+    pub created_at: i64,
+    pub kind: String,
+    pub player_name: String,
+    pub kept_dice: String,
+    pub dice: String,
+    pub booking_type: String,
+    pub column_index: i32,
+    pub points: i32,
+    pub details: String,
+    pub undone_sequence: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::game_updates)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct GameUpdateRecord {
+    pub id: i32,
+    pub game_id: String,
+    pub message: String,
+    pub created_at: i64,
 }
 
 #[derive(QueryableByName, Debug)]
@@ -0,0 +1,127 @@
+use crate::data_persistence::{delete_game_updates_older_than, insert_game_update, latest_game_update_id, load_game_updates_after};
+use rocket::tokio::sync::broadcast::{self, Receiver, Sender};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Number of updates a slow subscriber may fall behind before it misses some.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// How often the `DatabaseNotifier` looks for new updates.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long stored updates are kept for the `DatabaseNotifier`s of all instances.
+const UPDATE_RETENTION: Duration = Duration::from_secs(60);
+
+/// Delivers updates of a game to every client watching it.
+///
+/// Updates are serialized messages, so a backend shared by several server instances, e.g. a message broker,
+/// can pass them on unchanged. A backend publishes every message to the subscribers of all instances
+/// and hands out receivers for the subscribers of its own instance.
+pub trait GameNotifier: Send + Sync {
+    /// Sends `message` to everyone subscribed to the game `game_id`.
+    fn publish(&self, game_id: &str, message: String);
+
+    /// Receives every message published for the game `game_id` from now on.
+    fn subscribe(&self, game_id: &str) -> Receiver<String>;
+}
+
+/// The notifier selected by `GAME_NOTIFIER`: IN_PROCESS (default) for a single server instance, DATABASE for
+/// several instances sharing the database.
+pub fn notifier_from_env() -> Result<Box<dyn GameNotifier>, String> {
+    let backend = env::var("GAME_NOTIFIER").unwrap_or_else(|_| "IN_PROCESS".to_string());
+    match backend.as_str() {
+        "IN_PROCESS" => Ok(Box::new(InProcessNotifier::new())),
+        "DATABASE" => Ok(Box::new(DatabaseNotifier::start()?)),
+        other => Err(format!("Unknown GAME_NOTIFIER {}", other)),
+    }
+}
+
+/// Notifier for a single server instance, the default: one broadcast channel per watched game.
+pub struct InProcessNotifier {
+    channels: Mutex<HashMap<String, Sender<String>>>,
+}
+
+impl InProcessNotifier {
+    pub fn new() -> Self {
+        InProcessNotifier { channels: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl Default for InProcessNotifier {
+    fn default() -> Self {
+        InProcessNotifier::new()
+    }
+}
+
+impl GameNotifier for InProcessNotifier {
+    fn publish(&self, game_id: &str, message: String) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(sender) = channels.get(game_id) {
+            // nobody is watching the game anymore once sending fails
+            if sender.send(message).is_err() {
+                channels.remove(game_id);
+            }
+        }
+    }
+
+    fn subscribe(&self, game_id: &str) -> Receiver<String> {
+        let mut channels = self.channels.lock().unwrap();
+        // drop the channels of games nobody watches anymore
+        channels.retain(|_, sender| sender.receiver_count() > 0);
+        channels
+            .entry(game_id.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+}
+
+/// Notifier for several server instances sharing the database: updates are stored in the `game_updates` table,
+/// which every instance polls to pass new updates on to its own subscribers. Updates reach the subscribers
+/// within `POLL_INTERVAL`, also those of the instance that published them.
+pub struct DatabaseNotifier {
+    local: Arc<InProcessNotifier>,
+}
+
+impl DatabaseNotifier {
+    /// Starts polling for the updates stored from now on.
+    pub fn start() -> Result<Self, String> {
+        let local = Arc::new(InProcessNotifier::new());
+        let mut last_seen = latest_game_update_id().map_err(|error| error.to_string())?;
+        let subscribers = Arc::clone(&local);
+        thread::spawn(move || {
+            let mut last_cleanup = Instant::now();
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                match load_game_updates_after(last_seen) {
+                    Ok(updates) => for update in updates {
+                        last_seen = update.id;
+                        subscribers.publish(&update.game_id, update.message);
+                    },
+                    Err(error) => eprintln!("Could not load game updates: {}", error),
+                }
+                if last_cleanup.elapsed() >= UPDATE_RETENTION {
+                    if let Err(error) = delete_game_updates_older_than(UPDATE_RETENTION) {
+                        eprintln!("Could not delete old game updates: {}", error);
+                    }
+                    last_cleanup = Instant::now();
+                }
+            }
+        });
+        Ok(DatabaseNotifier { local })
+    }
+}
+
+impl GameNotifier for DatabaseNotifier {
+    fn publish(&self, game_id: &str, message: String) {
+        if let Err(error) = insert_game_update(game_id, &message) {
+            eprintln!("Could not store update of game {}: {}", game_id, error);
+        }
+    }
+
+    fn subscribe(&self, game_id: &str) -> Receiver<String> {
+        self.local.subscribe(game_id)
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt;
+use crate::game_logic::{BookingType, KniffelPlayer, ScoreColumn};
+use crate::scoring::*;
+
+/// The rules of a dice game variant: its categories, how they score, its bonuses and when a player is done.
+pub trait RuleSet: fmt::Debug + Send + Sync {
+    /// Unique name, used to select the rule set when creating a game and to store it with the game.
+    fn name(&self) -> &'static str;
+
+    /// All categories of the scorecard, in scorecard order.
+    fn categories(&self) -> &'static [BookingType];
+
+    /// Number of dice on the table.
+    fn dice_count(&self) -> usize {
+        5
+    }
+
+    /// True if throws a player does not use are saved for their later turns.
+    fn saves_rolls(&self) -> bool {
+        false
+    }
+
+    /// Points for booking `dice_rolls` into `booking_type`. `served` is true if the dice come from the first throw of the turn.
+    fn score(&self, booking_type: BookingType, dice_rolls: &[i32], served: bool) -> i32;
+
+    /// Bonus for the sum of ONES..SIXES.
+    fn upper_bonus(&self, upper_score: i32) -> i32;
+
+    /// Points for every extra Kniffel once the KNIFFEL box was booked with points, 0 if there is no such bonus.
+    fn extra_kniffel_bonus(&self) -> i32 {
+        0
+    }
+
+    /// True if an extra Kniffel is played as a joker, see `KniffelGame::book_dice_roll`.
+    fn has_joker_rules(&self) -> bool {
+        false
+    }
+
+    /// Points for booking an extra Kniffel played as a joker into `booking_type`.
+    fn joker_score(&self, booking_type: BookingType, dice_rolls: &[i32]) -> i32 {
+        self.score(booking_type, dice_rolls, false)
+    }
+
+    /// True once every category of `column` is booked.
+    fn is_column_full(&self, column: &ScoreColumn) -> bool {
+        self.categories().iter().all(|booking_type| column.has_used_booking_type(booking_type))
+    }
+
+    /// End condition: true once `player` has booked every category in every column.
+    fn is_finished(&self, player: &KniffelPlayer) -> bool {
+        player.columns.iter().all(|column| self.is_column_full(column))
+    }
+}
+
+/// Kniffel and Yahtzee: fixed scores for Full House and the straights, a 35 point bonus at 63
+/// and joker rules for extra Kniffels. Both only differ in the extra Kniffel bonus.
+#[derive(Debug)]
+pub struct ClassicRules {
+    name: &'static str,
+    extra_kniffel_bonus: i32,
+}
+
+const CLASSIC_CATEGORIES: [BookingType; 13] = [
+    BookingType::Ones,
+    BookingType::Twos,
+    BookingType::Threes,
+    BookingType::Fours,
+    BookingType::Fives,
+    BookingType::Sixes,
+    BookingType::ThreeOfAKind,
+    BookingType::FourOfAKind,
+    BookingType::FullHouse,
+    BookingType::SmallStraight,
+    BookingType::LargeStraight,
+    BookingType::Kniffel,
+    BookingType::Chance,
+];
+
+impl RuleSet for ClassicRules {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn categories(&self) -> &'static [BookingType] {
+        &CLASSIC_CATEGORIES
+    }
+
+    fn score(&self, booking_type: BookingType, dice_rolls: &[i32], _served: bool) -> i32 {
+        let fixed_score = |matches: bool, points: i32| if matches { points } else { 0 };
+        match booking_type {
+            BookingType::ThreeOfAKind => get_score_for_x_of_a_kind(dice_rolls, 3),
+            BookingType::FourOfAKind => get_score_for_x_of_a_kind(dice_rolls, 4),
+            BookingType::FullHouse => fixed_score(is_full_house(dice_rolls), 25),
+            BookingType::SmallStraight => fixed_score(is_small_straight(dice_rolls), 30),
+            BookingType::LargeStraight => fixed_score(is_large_straight(dice_rolls), 40),
+            BookingType::Kniffel => fixed_score(is_kniffel(dice_rolls), 50),
+            BookingType::Chance => get_score_chance(dice_rolls),
+            _ => score_upper_section(booking_type, dice_rolls),
+        }
+    }
+
+    fn upper_bonus(&self, upper_score: i32) -> i32 {
+        if upper_score >= 63 { 35 } else { 0 }
+    }
+
+    fn extra_kniffel_bonus(&self) -> i32 {
+        self.extra_kniffel_bonus
+    }
+
+    fn has_joker_rules(&self) -> bool {
+        true
+    }
+
+    fn joker_score(&self, booking_type: BookingType, dice_rolls: &[i32]) -> i32 {
+        match booking_type {
+            BookingType::FullHouse => 25,
+            BookingType::SmallStraight => 30,
+            BookingType::LargeStraight => 40,
+            _ => self.score(booking_type, dice_rolls, false),
+        }
+    }
+}
+
+/// Scandinavian Yatzy: pairs, x of a kind counting only the matching dice, fixed-score straights,
+/// a Full House worth its dice and a 50 point bonus at 63.
+#[derive(Debug)]
+pub struct YatzyRules;
+
+const YATZY_CATEGORIES: [BookingType; 15] = [
+    BookingType::Ones,
+    BookingType::Twos,
+    BookingType::Threes,
+    BookingType::Fours,
+    BookingType::Fives,
+    BookingType::Sixes,
+    BookingType::OnePair,
+    BookingType::TwoPairs,
+    BookingType::ThreeOfAKind,
+    BookingType::FourOfAKind,
+    BookingType::SmallStraight,
+    BookingType::LargeStraight,
+    BookingType::FullHouse,
+    BookingType::Chance,
+    BookingType::Kniffel,
+];
+
+impl RuleSet for YatzyRules {
+    fn name(&self) -> &'static str {
+        "YATZY"
+    }
+
+    fn categories(&self) -> &'static [BookingType] {
+        &YATZY_CATEGORIES
+    }
+
+    fn score(&self, booking_type: BookingType, dice_rolls: &[i32], _served: bool) -> i32 {
+        let fixed_score = |matches: bool, points: i32| if matches { points } else { 0 };
+        match booking_type {
+            BookingType::OnePair => get_score_pairs(dice_rolls, 1),
+            BookingType::TwoPairs => get_score_pairs(dice_rolls, 2),
+            BookingType::ThreeOfAKind => get_score_for_x_of_a_kind_only(dice_rolls, 3),
+            BookingType::FourOfAKind => get_score_for_x_of_a_kind_only(dice_rolls, 4),
+            BookingType::SmallStraight => fixed_score(contains_sequence(dice_rolls, &[1, 2, 3, 4, 5]), 15),
+            BookingType::LargeStraight => fixed_score(contains_sequence(dice_rolls, &[2, 3, 4, 5, 6]), 20),
+            BookingType::FullHouse => fixed_score(is_full_house(dice_rolls), get_score_chance(dice_rolls)),
+            BookingType::Chance => get_score_chance(dice_rolls),
+            BookingType::Kniffel => fixed_score(is_kniffel(dice_rolls), 50),
+            _ => score_upper_section(booking_type, dice_rolls),
+        }
+    }
+
+    fn upper_bonus(&self, upper_score: i32) -> i32 {
+        if upper_score >= 63 { 50 } else { 0 }
+    }
+}
+
+/// Generala: no upper bonus, fixed scores for Escalera (LARGE_STRAIGHT), Full (FULL_HOUSE),
+/// Poker (FOUR_OF_A_KIND) and Generala (KNIFFEL), with 5 extra points if they are served in the first throw.
+#[derive(Debug)]
+pub struct GeneralaRules;
+
+const GENERALA_CATEGORIES: [BookingType; 10] = [
+    BookingType::Ones,
+    BookingType::Twos,
+    BookingType::Threes,
+    BookingType::Fours,
+    BookingType::Fives,
+    BookingType::Sixes,
+    BookingType::LargeStraight,
+    BookingType::FullHouse,
+    BookingType::FourOfAKind,
+    BookingType::Kniffel,
+];
+
+impl RuleSet for GeneralaRules {
+    fn name(&self) -> &'static str {
+        "GENERALA"
+    }
+
+    fn categories(&self) -> &'static [BookingType] {
+        &GENERALA_CATEGORIES
+    }
+
+    fn score(&self, booking_type: BookingType, dice_rolls: &[i32], served: bool) -> i32 {
+        let served_score = |matches: bool, points: i32| match (matches, served) {
+            (true, true) => points + 5,
+            (true, false) => points,
+            (false, _) => 0,
+        };
+        match booking_type {
+            BookingType::LargeStraight => served_score(is_large_straight(dice_rolls), 20),
+            BookingType::FullHouse => served_score(is_full_house(dice_rolls), 30),
+            BookingType::FourOfAKind => served_score(get_score_for_x_of_a_kind(dice_rolls, 4) > 0, 40),
+            BookingType::Kniffel => if is_kniffel(dice_rolls) { 50 } else { 0 },
+            _ => score_upper_section(booking_type, dice_rolls),
+        }
+    }
+
+    fn upper_bonus(&self, _upper_score: i32) -> i32 {
+        0
+    }
+}
+
+/// Maxi Yatzy: Yatzy with six dice, extra categories, a 100 point bonus at 84 and unused throws
+/// carried over to later turns. Maxi Yatzy (KNIFFEL) is worth 100.
+#[derive(Debug)]
+pub struct MaxiYatzyRules;
+
+const MAXI_YATZY_CATEGORIES: [BookingType; 20] = [
+    BookingType::Ones,
+    BookingType::Twos,
+    BookingType::Threes,
+    BookingType::Fours,
+    BookingType::Fives,
+    BookingType::Sixes,
+    BookingType::OnePair,
+    BookingType::TwoPairs,
+    BookingType::ThreePairs,
+    BookingType::ThreeOfAKind,
+    BookingType::FourOfAKind,
+    BookingType::FiveOfAKind,
+    BookingType::SmallStraight,
+    BookingType::LargeStraight,
+    BookingType::FullStraight,
+    BookingType::FullHouse,
+    BookingType::Castle,
+    BookingType::Tower,
+    BookingType::Chance,
+    BookingType::Kniffel,
+];
+
+impl RuleSet for MaxiYatzyRules {
+    fn name(&self) -> &'static str {
+        "MAXI_YATZY"
+    }
+
+    fn categories(&self) -> &'static [BookingType] {
+        &MAXI_YATZY_CATEGORIES
+    }
+
+    fn dice_count(&self) -> usize {
+        6
+    }
+
+    fn saves_rolls(&self) -> bool {
+        true
+    }
+
+    fn score(&self, booking_type: BookingType, dice_rolls: &[i32], _served: bool) -> i32 {
+        let fixed_score = |matches: bool, points: i32| if matches { points } else { 0 };
+        match booking_type {
+            BookingType::OnePair => get_score_pairs(dice_rolls, 1),
+            BookingType::TwoPairs => get_score_pairs(dice_rolls, 2),
+            BookingType::ThreePairs => get_score_pairs(dice_rolls, 3),
+            BookingType::ThreeOfAKind => get_score_for_x_of_a_kind_only(dice_rolls, 3),
+            BookingType::FourOfAKind => get_score_for_x_of_a_kind_only(dice_rolls, 4),
+            BookingType::FiveOfAKind => get_score_for_x_of_a_kind_only(dice_rolls, 5),
+            BookingType::SmallStraight => fixed_score(contains_sequence(dice_rolls, &[1, 2, 3, 4, 5]), 15),
+            BookingType::LargeStraight => fixed_score(contains_sequence(dice_rolls, &[2, 3, 4, 5, 6]), 20),
+            BookingType::FullStraight => fixed_score(contains_sequence(dice_rolls, &[1, 2, 3, 4, 5, 6]), 21),
+            BookingType::FullHouse => get_score_value_groups(dice_rolls, &[3, 2]),
+            BookingType::Castle => get_score_value_groups(dice_rolls, &[3, 3]),
+            BookingType::Tower => get_score_value_groups(dice_rolls, &[4, 2]),
+            BookingType::Chance => get_score_chance(dice_rolls),
+            BookingType::Kniffel => fixed_score(is_kniffel(dice_rolls), 100),
+            _ => score_upper_section(booking_type, dice_rolls),
+        }
+    }
+
+    fn upper_bonus(&self, upper_score: i32) -> i32 {
+        if upper_score >= 84 { 100 } else { 0 }
+    }
+}
+
+/// Scores ONES..SIXES, every other category scores 0.
+fn score_upper_section(booking_type: BookingType, dice_rolls: &[i32]) -> i32 {
+    match booking_type {
+        BookingType::Ones => get_score_1_to_6(dice_rolls, 1),
+        BookingType::Twos => get_score_1_to_6(dice_rolls, 2),
+        BookingType::Threes => get_score_1_to_6(dice_rolls, 3),
+        BookingType::Fours => get_score_1_to_6(dice_rolls, 4),
+        BookingType::Fives => get_score_1_to_6(dice_rolls, 5),
+        BookingType::Sixes => get_score_1_to_6(dice_rolls, 6),
+        _ => 0,
+    }
+}
+
+pub static KNIFFEL: ClassicRules = ClassicRules { name: "KNIFFEL", extra_kniffel_bonus: 50 };
+pub static YAHTZEE: ClassicRules = ClassicRules { name: "YAHTZEE", extra_kniffel_bonus: 100 };
+pub static YATZY: YatzyRules = YatzyRules;
+pub static GENERALA: GeneralaRules = GeneralaRules;
+pub static MAXI_YATZY: MaxiYatzyRules = MaxiYatzyRules;
+
+/// All rule sets a game can be created with.
+pub static RULE_SETS: [&dyn RuleSet; 5] = [&KNIFFEL, &YAHTZEE, &YATZY, &GENERALA, &MAXI_YATZY];
+
+/// Looks up a rule set by its name.
+pub fn rule_set_by_name(name: &str) -> Option<&'static dyn RuleSet> {
+    RULE_SETS.iter().copied().find(|rule_set| rule_set.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_rules_score_fixed_patterns() {
+        assert_eq!(KNIFFEL.score(BookingType::FullHouse, &[2, 2, 5, 5, 5], false), 25);
+        assert_eq!(KNIFFEL.score(BookingType::SmallStraight, &[1, 2, 3, 4, 6], false), 30);
+        assert_eq!(KNIFFEL.score(BookingType::LargeStraight, &[1, 2, 3, 4, 6], false), 0);
+        assert_eq!(KNIFFEL.score(BookingType::ThreeOfAKind, &[4, 4, 4, 1, 2], false), 15);
+        assert_eq!(KNIFFEL.score(BookingType::Kniffel, &[3, 3, 3, 3, 3], false), 50);
+        assert_eq!(KNIFFEL.upper_bonus(62), 0);
+        assert_eq!(KNIFFEL.upper_bonus(63), 35);
+    }
+
+    #[test]
+    fn kniffel_and_yahtzee_only_differ_in_the_extra_kniffel_bonus() {
+        assert_eq!(KNIFFEL.extra_kniffel_bonus(), 50);
+        assert_eq!(YAHTZEE.extra_kniffel_bonus(), 100);
+        assert_eq!(YAHTZEE.joker_score(BookingType::LargeStraight, &[2, 2, 2, 2, 2]), 40);
+        assert_eq!(YAHTZEE.joker_score(BookingType::Chance, &[2, 2, 2, 2, 2]), 10);
+    }
+
+    #[test]
+    fn yatzy_counts_only_the_matching_dice() {
+        assert_eq!(YATZY.score(BookingType::OnePair, &[2, 2, 5, 5, 6], false), 10);
+        assert_eq!(YATZY.score(BookingType::TwoPairs, &[2, 2, 5, 5, 6], false), 14);
+        assert_eq!(YATZY.score(BookingType::ThreeOfAKind, &[4, 4, 4, 1, 2], false), 12);
+        assert_eq!(YATZY.score(BookingType::FullHouse, &[2, 2, 5, 5, 5], false), 19);
+        assert_eq!(YATZY.score(BookingType::SmallStraight, &[1, 2, 3, 4, 5], false), 15);
+        assert_eq!(YATZY.score(BookingType::SmallStraight, &[2, 3, 4, 5, 6], false), 0);
+        assert_eq!(YATZY.upper_bonus(63), 50);
+        assert!(!YATZY.has_joker_rules());
+    }
+
+    #[test]
+    fn generala_rewards_served_patterns() {
+        assert_eq!(GENERALA.score(BookingType::FullHouse, &[2, 2, 5, 5, 5], false), 30);
+        assert_eq!(GENERALA.score(BookingType::FullHouse, &[2, 2, 5, 5, 5], true), 35);
+        assert_eq!(GENERALA.score(BookingType::FourOfAKind, &[1, 1, 1, 1, 6], true), 45);
+        assert_eq!(GENERALA.score(BookingType::Kniffel, &[6, 6, 6, 6, 6], true), 50);
+        assert_eq!(GENERALA.upper_bonus(105), 0);
+    }
+
+    #[test]
+    fn maxi_yatzy_scores_six_dice() {
+        assert_eq!(MAXI_YATZY.dice_count(), 6);
+        assert_eq!(MAXI_YATZY.score(BookingType::ThreePairs, &[1, 1, 3, 3, 6, 6], false), 20);
+        assert_eq!(MAXI_YATZY.score(BookingType::Castle, &[2, 2, 2, 5, 5, 5], false), 21);
+        assert_eq!(MAXI_YATZY.score(BookingType::Tower, &[4, 4, 4, 4, 1, 1], false), 18);
+        assert_eq!(MAXI_YATZY.score(BookingType::FullStraight, &[1, 2, 3, 4, 5, 6], false), 21);
+        assert_eq!(MAXI_YATZY.score(BookingType::Kniffel, &[3, 3, 3, 3, 3, 3], false), 100);
+        assert_eq!(MAXI_YATZY.upper_bonus(84), 100);
+    }
+
+    #[test]
+    fn rule_sets_are_found_by_name() {
+        assert_eq!(rule_set_by_name("GENERALA").map(|rule_set| rule_set.name()), Some("GENERALA"));
+        assert!(rule_set_by_name("POKER").is_none());
+    }
+}
@@ -1,5 +1,54 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    turns (id) {
+        id -> Integer,
+        game_id -> Integer,
+        #[max_length = 255]
+        player_name -> Varchar,
+        rolls -> Text,
+        kept_dice -> Text,
+        #[max_length = 255]
+        booking_type -> Varchar,
+        column_index -> Integer,
+        points -> Integer,
+    }
+}
+
+diesel::table! {
+    game_events (id) {
+        id -> Integer,
+        game_id -> Integer,
+        sequence_number -> Integer,
+        created_at -> Bigint,
+        #[max_length = 255]
+        kind -> Varchar,
+        #[max_length = 255]
+        player_name -> Varchar,
+        #[max_length = 255]
+        kept_dice -> Varchar,
+        #[max_length = 255]
+        dice -> Varchar,
+        #[max_length = 255]
+        booking_type -> Varchar,
+        column_index -> Integer,
+        points -> Integer,
+        #[max_length = 4096]
+        details -> Varchar,
+        undone_sequence -> Integer,
+    }
+}
+
+diesel::table! {
+    game_updates (id) {
+        id -> Integer,
+        #[max_length = 255]
+        game_id -> Varchar,
+        message -> Text,
+        created_at -> Bigint,
+    }
+}
+
 diesel::table! {
     games (id) {
         id -> Integer,
@@ -12,6 +61,25 @@ diesel::table! {
         dice_rolls -> Varchar,
         #[max_length = 255]
         current_player -> Varchar,
+        #[max_length = 255]
+        rule_set -> Varchar,
+        dice_seed -> Bigint,
+        dice_position -> Bigint,
+        column_count -> Integer,
+        #[max_length = 255]
+        announcement -> Varchar,
+        turn_rolls -> Text,
+        turn_kept_dice -> Text,
+        event_count -> Integer,
+        #[max_length = 255]
+        undo_policy -> Varchar,
+        undo_limit -> Integer,
+        #[max_length = 255]
+        undo_requested_by -> Varchar,
+        #[max_length = 1024]
+        undo_approved_by -> Varchar,
+        #[max_length = 255]
+        spectator_token -> Varchar,
     }
 }
 
@@ -22,8 +90,31 @@ diesel::table! {
         #[max_length = 255]
         name -> Varchar,
         score -> Integer,
+        seat -> Integer,
+        saved_rolls -> Integer,
+        hint_count -> Integer,
+        #[max_length = 255]
+        bot -> Varchar,
+        undo_count -> Integer,
+        #[max_length = 255]
+        token -> Varchar,
+    }
+}
+
+diesel::table! {
+    player_columns (id) {
+        id -> Integer,
+        player_id -> Integer,
+        column_index -> Integer,
+        upper_score -> Integer,
+        bonus -> Integer,
+        lower_score -> Integer,
+        scored_kniffel -> Bool,
+        kniffel_bonus_count -> Integer,
         #[max_length = 255]
         used_booking_types -> Varchar,
+        #[max_length = 255]
+        column_order -> Varchar,
     }
 }
 
@@ -34,6 +125,10 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    game_events,
+    game_updates,
     games,
+    player_columns,
     players,
+    turns,
 );
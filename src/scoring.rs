@@ -1,26 +1,32 @@
-
-
+use std::collections::HashMap;
 
 /// Calculates the score for a specific value (1 to 6) based on its occurrences in `dice_rolls`.
-pub fn get_score_1_to_6(dice_rolls: &[i32; 5], value_to_score: i32) -> i32 {
+pub fn get_score_1_to_6(dice_rolls: &[i32], value_to_score: i32) -> i32 {
     dice_rolls
         .iter() // Iterate over the dice_rolls array
         .filter(|&&roll| roll == value_to_score) // Filter for the value_to_score
         .count() as i32 * value_to_score // Multiply the count by value_to_score
 }
 
-/// Calculates the score for X of a kind, where X is specified by `value_to_score`.
-pub fn get_score_for_x_of_a_kind(dice_rolls: &[i32; 5], value_to_score: i32) -> i32 {
+/// Counts the occurrences of each dice value, index 0 holds the number of ones.
+pub fn count_values(dice_rolls: &[i32]) -> [i32; 6] {
     // Create an array to count occurrences of each value (1 through 6)
     let mut counts = [0; 6];
 
     // Count the occurrences of each dice value
     for &roll in dice_rolls {
-        if roll >= 1 && roll <= 6 {
+        if (1..=6).contains(&roll) {
             counts[(roll - 1) as usize] += 1;
         }
     }
 
+    counts
+}
+
+/// Calculates the score for X of a kind, where X is specified by `value_to_score`.
+pub fn get_score_for_x_of_a_kind(dice_rolls: &[i32], value_to_score: i32) -> i32 {
+    let counts = count_values(dice_rolls);
+
     // Check if any value appears exactly `value_to_score` times
     if counts.iter().any(|&count| count >= value_to_score) {
         // If such a value exists, return the sum of all dice rolls
@@ -31,36 +37,62 @@ pub fn get_score_for_x_of_a_kind(dice_rolls: &[i32; 5], value_to_score: i32) ->
     }
 }
 
-/// Calculates the score for a Full House. Returns 25 if it is a Full House, otherwise 0.
-pub fn get_score_full_house(dice_rolls: &[i32; 5]) -> i32 {
-    // Create an array to count occurrences of each value (1 through 6)
-    let mut counts = [0; 6];
-
-    // Count the occurrences of each dice value
-    for &roll in dice_rolls {
-        if roll >= 1 && roll <= 6 {
-            counts[(roll - 1) as usize] += 1;
+/// Calculates the best score for groups of dice with different values, e.g. `[3, 2]` for a Full House
+/// of three and two dice. Only the dice in the groups are counted; returns 0 if the groups cannot be formed.
+pub fn get_score_value_groups(dice_rolls: &[i32], group_sizes: &[i32]) -> i32 {
+    fn best_score(counts: &[i32; 6], used: &mut [bool; 6], group_sizes: &[i32]) -> Option<i32> {
+        let (&group_size, remaining_groups) = match group_sizes.split_first() {
+            Some(split) => split,
+            None => return Some(0),
+        };
+        let mut best: Option<i32> = None;
+        for value in 1..=6 {
+            let index = (value - 1) as usize;
+            if used[index] || counts[index] < group_size {
+                continue;
+            }
+            used[index] = true;
+            if let Some(rest) = best_score(counts, used, remaining_groups) {
+                best = best.max(Some(value * group_size + rest));
+            }
+            used[index] = false;
         }
+        best
     }
 
+    best_score(&count_values(dice_rolls), &mut [false; 6], group_sizes).unwrap_or(0)
+}
+
+/// Calculates the score for X of a kind counting only the matching dice, e.g. 3 x 5 for three fives.
+/// If several values qualify the highest one is used.
+pub fn get_score_for_x_of_a_kind_only(dice_rolls: &[i32], value_to_score: i32) -> i32 {
+    get_score_value_groups(dice_rolls, &[value_to_score])
+}
+
+/// Calculates the score for `pair_count` pairs of different values, using the highest pairs.
+/// Returns the sum of the paired dice, or 0 if there are not enough pairs.
+pub fn get_score_pairs(dice_rolls: &[i32], pair_count: usize) -> i32 {
+    get_score_value_groups(dice_rolls, &vec![2; pair_count])
+}
+
+/// Checks for a Full House, exactly three dice of one value and two of another.
+pub fn is_full_house(dice_rolls: &[i32]) -> bool {
+    let counts = count_values(dice_rolls);
+
     // Check if there's a Full House (one 3-count and one 2-count)
-    let has_three_of_a_kind = counts.iter().any(|&count| count == 3);
-    let has_two_of_a_kind = counts.iter().any(|&count| count == 2);
+    let has_three_of_a_kind = counts.contains(&3);
+    let has_two_of_a_kind = counts.contains(&2);
 
-    if has_three_of_a_kind && has_two_of_a_kind {
-        25
-    } else {
-        0
-    }
+    has_three_of_a_kind && has_two_of_a_kind
 }
 
-/// Calculates the score for a Small Straight. Returns 30 if it is a Small Straight, otherwise 0.
-pub fn get_score_small_straight(dice_rolls: &[i32; 5]) -> i32 {
-    // Create a set to store unique dice rolls
-    let mut unique_rolls = dice_rolls.to_vec();
-    unique_rolls.sort_unstable();
-    unique_rolls.dedup();  // Remove duplicates
+/// Checks if every value of `sequence` is on the table.
+pub fn contains_sequence(dice_rolls: &[i32], sequence: &[i32]) -> bool {
+    sequence.iter().all(|num| dice_rolls.contains(num))
+}
 
+/// Checks for a Small Straight, four consecutive values.
+pub fn is_small_straight(dice_rolls: &[i32]) -> bool {
     // Check for the presence of any of the valid Small Straight sequences
     let small_straights = [
         [1, 2, 3, 4], // 1-2-3-4
@@ -68,49 +100,222 @@ pub fn get_score_small_straight(dice_rolls: &[i32; 5]) -> i32 {
         [3, 4, 5, 6], // 3-4-5-6
     ];
 
-    for straight in small_straights.iter() {
-        if straight.iter().all(|&num| unique_rolls.contains(&num)) {
-            return 30;
-        }
-    }
-
-    0
+    small_straights.iter().any(|straight| contains_sequence(dice_rolls, straight))
 }
 
-/// Calculates the score for a Large Straight. Returns 40 if it is a Large Straight, otherwise 0.
-pub fn get_score_large_straight(dice_rolls: &[i32; 5]) -> i32 {
-    // Create a vector to store the dice rolls
-    let mut sorted_rolls = dice_rolls.to_vec();
-    sorted_rolls.sort_unstable();  // Sort the rolls
-    sorted_rolls.dedup();  // Remove duplicates
-
+/// Checks for a Large Straight, five consecutive values.
+pub fn is_large_straight(dice_rolls: &[i32]) -> bool {
     // There are only two valid sequences for a Large Straight
     let large_straights = [
         [1, 2, 3, 4, 5],  // Sequence 1-2-3-4-5
         [2, 3, 4, 5, 6],  // Sequence 2-3-4-5-6
     ];
 
-    // Check if the sorted rolls match either of the valid Large Straight sequences
-    for straight in large_straights.iter() {
-        if sorted_rolls == straight {
-            return 40;
+    large_straights.iter().any(|straight| contains_sequence(dice_rolls, straight))
+}
+
+/// Checks for a Kniffel (Yahtzee), all dice showing the same value.
+pub fn is_kniffel(dice_rolls: &[i32]) -> bool {
+    // Check if all dice rolls are the same
+    dice_rolls.iter().all(|&roll| roll == dice_rolls[0])
+}
+
+/// Calculates the score for Chance. Returns the sum of all dice.
+pub fn get_score_chance(dice_rolls: &[i32]) -> i32 {
+    dice_rolls.iter().sum()
+}
+
+/// Dice of a hand counted by value, index 0 holds the number of ones.
+type Hand = [u8; 6];
+
+fn hand_of(dice_rolls: &[i32]) -> Hand {
+    count_values(dice_rolls).map(|count| count as u8)
+}
+
+fn dice_of(hand: &Hand) -> Vec<i32> {
+    (1..=6).flat_map(|value| std::iter::repeat_n(value, hand[(value - 1) as usize] as usize)).collect()
+}
+
+/// Every hand of `dice_count` dice with the probability to throw it.
+fn throws(dice_count: u8) -> Vec<(Hand, f64)> {
+    fn fill(value: usize, left: u8, hand: &mut Hand, result: &mut Vec<Hand>) {
+        if value == 5 {
+            hand[5] = left;
+            result.push(*hand);
+            return;
+        }
+        for count in 0..=left {
+            hand[value] = count;
+            fill(value + 1, left - count, hand, result);
         }
     }
+    let factorial = |n: u8| (1..=n as u64).product::<u64>() as f64;
 
-    0
+    let mut hands = Vec::new();
+    fill(0, dice_count, &mut [0; 6], &mut hands);
+    hands.into_iter()
+        .map(|hand| {
+            let orderings = factorial(dice_count) / hand.iter().map(|&count| factorial(count)).product::<f64>();
+            (hand, orderings / 6f64.powi(dice_count as i32))
+        })
+        .collect()
 }
 
-/// Calculates the score for a Kniffel (Yahtzee). Returns 50 if it is a Kniffel, otherwise 0.
-pub fn get_score_kniffel(dice_rolls: &[i32; 5]) -> i32 {
-    // Check if all dice rolls are the same
-    if dice_rolls.iter().all(|&roll| roll == dice_rolls[0]) {
-        50
-    } else {
-        0
+/// Every hand that can be kept from `hand`, including the empty one and `hand` itself.
+fn kept_hands(hand: &Hand) -> Vec<Hand> {
+    let mut result = vec![[0; 6]];
+    for value in 0..6 {
+        result = result.into_iter()
+            .flat_map(|kept| (0..=hand[value]).map(move |count| {
+                let mut kept = kept;
+                kept[value] = count;
+                kept
+            }))
+            .collect();
     }
+    result
 }
 
-/// Calculates the score for Chance. Returns the sum of all dice.
-pub fn get_score_chance(dice_rolls: &[i32; 5]) -> i32 {
-    dice_rolls.iter().sum()
+/// Value of the dice at the end of a turn, e.g. the points of a category.
+pub type TurnTarget<'a> = &'a dyn Fn(&[i32]) -> f64;
+
+/// Best expected value of every target at the end of a turn, computed exactly by enumerating all throws.
+///
+/// The dice not in `dice_to_keep` are thrown once more, then every further throw of the `rolls_left` is played
+/// to maximize the target on its own. Keeping all dice ends the turn, so does `rolls_left` of 0; the targets are
+/// then taken from `dice_rolls` as they are.
+pub fn expected_turn_values(dice_rolls: &[i32], dice_to_keep: &[i32], rolls_left: i32,
+                            targets: &[TurnTarget]) -> Vec<f64> {
+    let evaluate = |dice: &[i32]| targets.iter().map(|target| target(dice)).collect::<Vec<f64>>();
+    let dice_count = dice_rolls.len();
+    if rolls_left <= 0 || dice_to_keep.len() >= dice_count {
+        return evaluate(dice_rolls);
+    }
+
+    let throws: Vec<Vec<(Hand, f64)>> = (0..=dice_count as u8).map(throws).collect();
+    let hands: Vec<Hand> = throws[dice_count].iter().map(|&(hand, _)| hand).collect();
+    let index: HashMap<Hand, usize> = hands.iter().enumerate().map(|(index, &hand)| (hand, index)).collect();
+    // expected values of the kept dice after throwing the others once
+    let expectation = |kept: &Hand, values: &[Vec<f64>]| {
+        let thrown = dice_count - kept.iter().map(|&count| count as usize).sum::<usize>();
+        let mut expected = vec![0.0; targets.len()];
+        for (outcome, probability) in &throws[thrown] {
+            let hand: Hand = std::array::from_fn(|value| kept[value] + outcome[value]);
+            for (sum, value) in expected.iter_mut().zip(&values[index[&hand]]) {
+                *sum += probability * value;
+            }
+        }
+        expected
+    };
+
+    // values of every hand with the throws still to come after it, starting with none
+    let mut values: Vec<Vec<f64>> = hands.iter().map(|hand| evaluate(&dice_of(hand))).collect();
+    for _ in 1..rolls_left {
+        let kept_values: HashMap<Hand, Vec<f64>> = throws.iter()
+            .flatten()
+            .map(|(kept, _)| (*kept, expectation(kept, &values)))
+            .collect();
+        values = hands.iter()
+            .map(|hand| kept_hands(hand).iter()
+                .map(|kept| &kept_values[kept])
+                .fold(vec![f64::MIN; targets.len()], |best, candidate| {
+                    best.iter().zip(candidate).map(|(&best, &candidate)| best.max(candidate)).collect()
+                }))
+            .collect();
+    }
+    expectation(&hand_of(dice_to_keep), &values)
+}
+
+/// Chances to end a turn with the dice patterns, see `pattern_probabilities`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PatternProbabilities {
+    pub kniffel: f64,
+    pub large_straight: f64,
+    pub small_straight: f64,
+    pub full_house: f64,
+    pub four_of_a_kind: f64,
+    pub three_of_a_kind: f64,
+}
+
+/// Exact probabilities to end the turn with each pattern when `dice_to_keep` are kept from `dice_rolls` and
+/// `rolls_left` throws remain. Each pattern is played for on its own, see `expected_turn_values`.
+pub fn pattern_probabilities(dice_rolls: &[i32], dice_to_keep: &[i32], rolls_left: i32) -> PatternProbabilities {
+    let chance = |pattern: fn(&[i32]) -> bool| move |dice: &[i32]| if pattern(dice) { 1.0 } else { 0.0 };
+    let of_a_kind = |count: i32| move |dice: &[i32]| {
+        if count_values(dice).iter().any(|&found| found >= count) { 1.0 } else { 0.0 }
+    };
+    let kniffel = chance(is_kniffel);
+    let large_straight = chance(is_large_straight);
+    let small_straight = chance(is_small_straight);
+    let full_house = chance(is_full_house);
+    let four_of_a_kind = of_a_kind(4);
+    let three_of_a_kind = of_a_kind(3);
+
+    let probabilities = expected_turn_values(dice_rolls, dice_to_keep, rolls_left,
+        &[&kniffel, &large_straight, &small_straight, &full_house, &four_of_a_kind, &three_of_a_kind]);
+    PatternProbabilities {
+        kniffel: probabilities[0],
+        large_straight: probabilities[1],
+        small_straight: probabilities[2],
+        full_house: probabilities[3],
+        four_of_a_kind: probabilities[4],
+        three_of_a_kind: probabilities[5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn patterns_of_the_dice_on_the_table_without_throws_left() {
+        let probabilities = pattern_probabilities(&[2, 2, 3, 3, 3], &[], 0);
+        assert_eq!(probabilities.full_house, 1.0);
+        assert_eq!(probabilities.three_of_a_kind, 1.0);
+        assert_eq!(probabilities.four_of_a_kind, 0.0);
+        assert_eq!(probabilities.kniffel, 0.0);
+    }
+
+    #[test]
+    fn patterns_with_one_die_left_to_throw() {
+        let probabilities = pattern_probabilities(&[6, 6, 6, 6, 1], &[6, 6, 6, 6], 1);
+        assert_close(probabilities.kniffel, 1.0 / 6.0);
+        assert_close(probabilities.four_of_a_kind, 1.0);
+
+        let probabilities = pattern_probabilities(&[2, 3, 4, 5, 5], &[2, 3, 4, 5], 1);
+        assert_close(probabilities.large_straight, 2.0 / 6.0);
+        assert_close(probabilities.small_straight, 1.0);
+    }
+
+    #[test]
+    fn patterns_are_played_for_again_with_every_throw_left() {
+        let probabilities = pattern_probabilities(&[6, 6, 6, 6, 1], &[6, 6, 6, 6], 2);
+        assert_close(probabilities.kniffel, 1.0 - (5.0 / 6.0) * (5.0 / 6.0));
+    }
+
+    #[test]
+    fn kniffel_chance_of_a_whole_turn() {
+        // the well-known 4.6 % of keeping the most frequent value for three throws
+        let probabilities = pattern_probabilities(&[1, 2, 3, 4, 6], &[], 3);
+        assert!((probabilities.kniffel - 0.04603).abs() < 1e-5, "got {}", probabilities.kniffel);
+    }
+
+    #[test]
+    fn expected_sum_of_a_whole_turn() {
+        let chance = |dice: &[i32]| get_score_chance(dice) as f64;
+        assert_close(expected_turn_values(&[1, 1, 1, 1, 1], &[], 1, &[&chance])[0], 17.5);
+        // a single die thrown up to three times is worth 14/3 when keeping a 5 or 6 after the first and a 4 or
+        // better after the second throw
+        assert_close(expected_turn_values(&[1, 1, 1, 1, 1], &[], 3, &[&chance])[0], 5.0 * 14.0 / 3.0);
+    }
+
+    #[test]
+    fn keeping_every_die_ends_the_turn() {
+        let chance = |dice: &[i32]| get_score_chance(dice) as f64;
+        assert_close(expected_turn_values(&[1, 2, 3, 4, 5], &[1, 2, 3, 4, 5], 2, &[&chance])[0], 15.0);
+    }
 }
@@ -0,0 +1,736 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+use lazy_static::lazy_static;
+use crate::game_logic::{BookingType, ColumnOrder, KniffelGame, MAX_ROLLS_PER_TURN, UPPER_SECTION};
+use crate::rule_set::{RuleSet, RULE_SETS};
+use crate::scoring::is_kniffel;
+
+/// Number of dice the solver plays with.
+const DICE: usize = 5;
+
+/// Highest possible sum of ONES..SIXES with five dice.
+const MAX_UPPER_SCORE: i32 = 105;
+
+/// Magic bytes of the value table cache files.
+const CACHE_MAGIC: &[u8; 6] = b"KNSOLV";
+
+/// Version of the cache file format and of the computation of the values, bump it whenever either changes.
+const CACHE_VERSION: u16 = 2;
+
+/// Dice as counts per value, index 0 holds the number of ones.
+type Counts = [u8; 6];
+
+/// All multisets of `dice` dice as counts per value.
+fn counts_of_size(dice: u8) -> Vec<Counts> {
+    fn fill(value: usize, left: u8, current: &mut Counts, result: &mut Vec<Counts>) {
+        if value == 5 {
+            current[5] = left;
+            result.push(*current);
+            return;
+        }
+        for count in 0..=left {
+            current[value] = count;
+            fill(value + 1, left - count, current, result);
+        }
+        current[value] = 0;
+    }
+    let mut result = Vec::new();
+    fill(0, dice, &mut [0; 6], &mut result);
+    result
+}
+
+fn factorial(n: u8) -> f32 {
+    (1..=n as u32).product::<u32>() as f32
+}
+
+/// Probability to throw exactly `counts` with as many dice as it holds.
+fn probability(counts: &Counts) -> f32 {
+    let dice: u8 = counts.iter().sum();
+    let permutations = factorial(dice) / counts.iter().map(|&count| factorial(count)).product::<f32>();
+    permutations / 6f32.powi(dice as i32)
+}
+
+fn to_dice(counts: &Counts) -> Vec<i32> {
+    counts.iter()
+        .enumerate()
+        .flat_map(|(index, &count)| std::iter::repeat_n(index as i32 + 1, count as usize))
+        .collect()
+}
+
+/// Every roll of five dice, every set of dice that can be kept and the transitions between them.
+#[derive(Debug)]
+struct DiceTables {
+    /// sorted dice of every roll
+    rolls: Vec<Vec<i32>>,
+    roll_index: HashMap<Counts, usize>,
+    /// probability of every roll when all dice are thrown
+    first_roll: Vec<f32>,
+    /// dice kept before a re-roll, from none to all five
+    keeps: Vec<Counts>,
+    /// rolls reachable from a keep by re-rolling the other dice, with their probability
+    keep_outcomes: Vec<Vec<(usize, f32)>>,
+    /// distinct keeps of every roll
+    roll_keeps: Vec<Vec<usize>>,
+}
+
+impl DiceTables {
+    fn new() -> Self {
+        let roll_counts = counts_of_size(DICE as u8);
+        let roll_index: HashMap<Counts, usize> = roll_counts.iter()
+            .enumerate()
+            .map(|(index, counts)| (*counts, index))
+            .collect();
+        let keeps: Vec<Counts> = (0..=DICE as u8).flat_map(counts_of_size).collect();
+
+        let keep_outcomes: Vec<Vec<(usize, f32)>> = keeps.iter()
+            .map(|keep| {
+                let thrown: u8 = DICE as u8 - keep.iter().sum::<u8>();
+                counts_of_size(thrown).iter()
+                    .map(|outcome| {
+                        let mut roll = *keep;
+                        for (count, extra) in roll.iter_mut().zip(outcome) {
+                            *count += extra;
+                        }
+                        (roll_index[&roll], probability(outcome))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let roll_keeps = roll_counts.iter()
+            .map(|roll| keeps.iter()
+                .enumerate()
+                .filter(|(_, keep)| keep.iter().zip(roll).all(|(kept, rolled)| kept <= rolled))
+                .map(|(index, _)| index)
+                .collect())
+            .collect();
+
+        let first_roll = roll_counts.iter().map(probability).collect();
+
+        DiceTables {
+            rolls: roll_counts.iter().map(to_dice).collect(),
+            roll_index,
+            first_roll,
+            keeps,
+            keep_outcomes,
+            roll_keeps,
+        }
+    }
+
+    fn counts(dice: &[i32]) -> Option<Counts> {
+        let mut counts = [0; 6];
+        for &value in dice {
+            if !(1..=6).contains(&value) {
+                return None;
+            }
+            counts[(value - 1) as usize] += 1;
+        }
+        Some(counts)
+    }
+}
+
+/// Expected values of one turn, by throw, for a fixed scorecard.
+struct TurnTables {
+    /// best booking of every roll, not served and served
+    book: [Vec<f32>; 2],
+    /// value of every keep with one and with two throws left
+    keep: [Vec<f32>; 2],
+}
+
+/// A decision of the current player.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Keep these dice and re-roll the others
+    Keep(Vec<i32>),
+
+    /// Book the dice on the table into this category
+    Book(BookingType),
+}
+
+/// A decision together with the final score it is expected to lead to with optimal play afterwards.
+#[derive(Debug, Clone)]
+pub struct Alternative {
+    pub decision: Decision,
+    pub expected_score: f64,
+}
+
+/// The situation of the current player as seen by the solver: one scorecard, the dice on the table and the
+/// re-rolls left this turn.
+#[derive(Debug, Clone)]
+pub struct Position {
+    /// bit i is set if category i of the rule set is open
+    open: u32,
+    upper_score: i32,
+    scored_kniffel: bool,
+    /// points already on the scorecard
+    score: i32,
+    dice: Vec<i32>,
+    rolls_left: i32,
+    /// true right after the first throw of the turn
+    served: bool,
+}
+
+/// Optimal solitaire strategy for a rule set: maximizes the expected final score of a single scorecard.
+///
+/// The value of every scorecard state (open categories × upper subtotal × scored-Kniffel flag) at the start of a
+/// turn is computed once by dynamic programming, from full scorecards backwards, and kept in a table.
+/// Within a turn the best keeps and bookings are derived from that table.
+#[derive(Debug)]
+pub struct Solver {
+    rule_set: &'static dyn RuleSet,
+    /// upper subtotals at or above this value all earn the same bonus
+    upper_cap: i32,
+    /// true if the scored-Kniffel flag changes future points
+    tracks_kniffel_bonus: bool,
+    tables: Arc<DiceTables>,
+    /// points for every roll and category, not served and served
+    scores: [Vec<i32>; 2],
+    /// hash of the rules the values are computed for, see `rules_fingerprint`
+    fingerprint: u64,
+    values: Vec<f32>,
+}
+
+lazy_static! {
+    /// Dice tables shared by the solvers of all rule sets.
+    static ref DICE_TABLES: Arc<DiceTables> = Arc::new(DiceTables::new());
+    /// One slot per rule set, filled once its value table is loaded or computed.
+    static ref SOLVERS: Mutex<HashMap<&'static str, Arc<OnceLock<Arc<Solver>>>>> = Mutex::new(HashMap::new());
+}
+
+/// True if the solver can play `rule_set`: five dice, three throws per turn and at most 16 categories.
+pub fn supports(rule_set: &dyn RuleSet) -> bool {
+    rule_set.dice_count() == DICE && !rule_set.saves_rolls() && rule_set.categories().len() <= 16
+}
+
+fn solver_slot(rule_set: &'static dyn RuleSet) -> Arc<OnceLock<Arc<Solver>>> {
+    let mut solvers = SOLVERS.lock().unwrap_or_else(PoisonError::into_inner);
+    solvers.entry(rule_set.name()).or_default().clone()
+}
+
+/// The solver for `rule_set`, None if the rule set is not supported.
+///
+/// The value table is loaded from the cache directory (`SOLVER_CACHE_DIR`, default `solver_cache`) or computed
+/// and written there on first use. Computing it takes a while and blocks the caller, as well as every other
+/// caller for the same rule set; later calls return the same solver instantly. Request handlers should use
+/// `ready_solver_for` and leave the computation to `warm_up`.
+pub fn solver_for(rule_set: &'static dyn RuleSet) -> Option<Arc<Solver>> {
+    if !supports(rule_set) {
+        return None;
+    }
+    Some(solver_slot(rule_set).get_or_init(|| Arc::new(Solver::load_or_compute(rule_set))).clone())
+}
+
+/// The solver for `rule_set` if its value table is ready, without waiting for it. None if the rule set is not
+/// supported or the table is still being loaded or computed.
+pub fn ready_solver_for(rule_set: &'static dyn RuleSet) -> Option<Arc<Solver>> {
+    if !supports(rule_set) {
+        return None;
+    }
+    solver_slot(rule_set).get().cloned()
+}
+
+/// Loads or computes the value tables of all supported rule sets, one after the other. Blocks until all are
+/// ready, the server runs it in the background at startup.
+pub fn warm_up() {
+    for rule_set in RULE_SETS.iter().copied().filter(|rule_set| supports(*rule_set)) {
+        solver_for(rule_set);
+    }
+}
+
+impl Solver {
+    fn empty(rule_set: &'static dyn RuleSet) -> Self {
+        let tables = DICE_TABLES.clone();
+        let upper_cap = (0..=MAX_UPPER_SCORE)
+            .find(|&upper| rule_set.upper_bonus(upper) == rule_set.upper_bonus(MAX_UPPER_SCORE))
+            .unwrap_or(0);
+        let tracks_kniffel_bonus = rule_set.extra_kniffel_bonus() > 0
+            && rule_set.categories().contains(&BookingType::Kniffel);
+        let score_table = |served: bool| tables.rolls.iter()
+            .flat_map(|dice| rule_set.categories().iter().map(move |&bt| rule_set.score(bt, dice, served)))
+            .collect();
+        let scores = [score_table(false), score_table(true)];
+        let mut solver = Solver { rule_set, upper_cap, tracks_kniffel_bonus, tables, scores, fingerprint: 0, values: Vec::new() };
+        solver.fingerprint = solver.rules_fingerprint();
+        solver.values = vec![0.0; solver.state_count()];
+        solver
+    }
+
+    /// Hash of everything the values depend on: the categories and every score, joker score and bonus of the
+    /// rule set. Stored with the cached values, so a cache computed under other rules is not used.
+    fn rules_fingerprint(&self) -> u64 {
+        let rule_set = self.rule_set;
+        let mut bytes = rule_set.name().as_bytes().to_vec();
+        for booking_type in rule_set.categories() {
+            bytes.push(b';');
+            bytes.extend(booking_type.to_string().bytes());
+        }
+        let joker_scores = self.tables.rolls.iter()
+            .flat_map(|dice| rule_set.categories().iter().map(move |&bt| rule_set.joker_score(bt, dice)));
+        let numbers = self.scores.iter().flatten().copied()
+            .chain(joker_scores)
+            .chain((0..=MAX_UPPER_SCORE).map(|upper| rule_set.upper_bonus(upper)))
+            .chain([rule_set.extra_kniffel_bonus(), rule_set.has_joker_rules() as i32, rule_set.dice_count() as i32]);
+        bytes.extend(numbers.flat_map(i32::to_le_bytes));
+        // FNV-1a, stable across Rust versions unlike the std hasher
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+    }
+
+    /// Magic bytes, format version and rules fingerprint at the start of the cache file.
+    fn cache_header(&self) -> Vec<u8> {
+        let mut header = CACHE_MAGIC.to_vec();
+        header.extend(CACHE_VERSION.to_le_bytes());
+        header.extend(self.fingerprint.to_le_bytes());
+        header
+    }
+
+    fn load_or_compute(rule_set: &'static dyn RuleSet) -> Self {
+        let mut solver = Solver::empty(rule_set);
+        let path = cache_path(rule_set);
+        match fs::read(&path).ok().and_then(|bytes| solver.read_values(&bytes)) {
+            Some(values) => solver.values = values,
+            None => {
+                solver.compute_values();
+                if let Err(error) = solver.write_cache(&path) {
+                    eprintln!("Could not write solver cache {}: {}", path.display(), error);
+                }
+            }
+        }
+        solver
+    }
+
+    fn flag_count(&self) -> usize {
+        if self.tracks_kniffel_bonus { 2 } else { 1 }
+    }
+
+    fn state_count(&self) -> usize {
+        (1usize << self.rule_set.categories().len()) * (self.upper_cap as usize + 1) * self.flag_count()
+    }
+
+    fn state_index(&self, open: u32, upper: i32, scored_kniffel: bool) -> usize {
+        let flag = if self.tracks_kniffel_bonus && scored_kniffel { 1 } else { 0 };
+        (open as usize * (self.upper_cap as usize + 1) + upper as usize) * self.flag_count() + flag
+    }
+
+    fn category_index(&self, booking_type: BookingType) -> Option<usize> {
+        self.rule_set.categories().iter().position(|&bt| bt == booking_type)
+    }
+
+    /// Expected points still to come at the start of a turn.
+    fn value(&self, open: u32, upper: i32, scored_kniffel: bool) -> f32 {
+        self.values[self.state_index(open, upper, scored_kniffel)]
+    }
+
+    /// Calls `booking` with the category index, the points gained including bonuses, and the next state
+    /// for every category the roll may be booked into, following the joker rules of the rule set.
+    fn for_each_booking(&self, open: u32, upper: i32, scored_kniffel: bool, roll: usize, served: bool,
+                        mut booking: impl FnMut(usize, i32, (u32, i32, bool))) {
+        let categories = self.rule_set.categories();
+        let dice = &self.tables.rolls[roll];
+        let kniffel_used = self.category_index(BookingType::Kniffel)
+            .is_some_and(|index| open & (1 << index) == 0);
+        let joker = self.rule_set.has_joker_rules() && kniffel_used && is_kniffel(dice);
+        let kniffel_bonus = if joker && scored_kniffel { self.rule_set.extra_kniffel_bonus() } else { 0 };
+
+        let mut allowed = open;
+        if joker {
+            let upper_mask = categories.iter()
+                .enumerate()
+                .filter(|(_, bt)| bt.is_upper_section())
+                .fold(0u32, |mask, (index, _)| mask | 1 << index);
+            let matching_upper_box = self.category_index(UPPER_SECTION[(dice[0] - 1) as usize])
+                .map(|index| 1u32 << index)
+                .unwrap_or(0);
+            allowed = if open & matching_upper_box != 0 {
+                matching_upper_box
+            } else if open & !upper_mask != 0 {
+                open & !upper_mask
+            } else {
+                open
+            };
+        }
+
+        for (index, &booking_type) in categories.iter().enumerate() {
+            if allowed & (1 << index) == 0 {
+                continue;
+            }
+            let score = if joker {
+                self.rule_set.joker_score(booking_type, dice)
+            } else {
+                self.scores[served as usize][roll * categories.len() + index]
+            };
+            let mut next_upper = upper;
+            let mut points = score + kniffel_bonus;
+            if booking_type.is_upper_section() {
+                next_upper = (upper + score).min(self.upper_cap);
+                points += self.rule_set.upper_bonus(next_upper) - self.rule_set.upper_bonus(upper);
+            }
+            let next_scored_kniffel = scored_kniffel || (booking_type == BookingType::Kniffel && score > 0);
+            booking(index, points, (open & !(1 << index), next_upper, next_scored_kniffel));
+        }
+    }
+
+    /// Best expected points of booking `roll` now, including the value of the scorecard afterwards.
+    fn best_booking(&self, open: u32, upper: i32, scored_kniffel: bool, roll: usize, served: bool) -> f32 {
+        let mut best = f32::NEG_INFINITY;
+        self.for_each_booking(open, upper, scored_kniffel, roll, served, |_, points, (next_open, next_upper, next_flag)| {
+            best = best.max(points as f32 + self.value(next_open, next_upper, next_flag));
+        });
+        best
+    }
+
+    fn turn_tables(&self, open: u32, upper: i32, scored_kniffel: bool) -> TurnTables {
+        let tables = &self.tables;
+        let book_served = |served: bool| (0..tables.rolls.len())
+            .map(|roll| self.best_booking(open, upper, scored_kniffel, roll, served))
+            .collect::<Vec<f32>>();
+        let book = [book_served(false), book_served(true)];
+        let keep_values = |roll_values: &[f32]| tables.keep_outcomes.iter()
+            .map(|outcomes| outcomes.iter().map(|&(roll, p)| p * roll_values[roll]).sum())
+            .collect::<Vec<f32>>();
+        let best_of = |roll: usize, book_value: f32, keep_values: &[f32]| tables.roll_keeps[roll].iter()
+            .map(|&keep| keep_values[keep])
+            .fold(book_value, f32::max);
+
+        // after the last throw only booking is left
+        let keep_last = keep_values(&book[0]);
+        let after_second: Vec<f32> = (0..tables.rolls.len())
+            .map(|roll| best_of(roll, book[0][roll], &keep_last))
+            .collect();
+        let keep_second = keep_values(&after_second);
+        TurnTables { book, keep: [keep_last, keep_second] }
+    }
+
+    /// Expected points of a turn starting with this scorecard.
+    fn turn_value(&self, open: u32, upper: i32, scored_kniffel: bool) -> f32 {
+        let tables = &self.tables;
+        let turn = self.turn_tables(open, upper, scored_kniffel);
+        tables.first_roll.iter()
+            .enumerate()
+            .map(|(roll, p)| {
+                let best = tables.roll_keeps[roll].iter()
+                    .map(|&keep| turn.keep[1][keep])
+                    .fold(turn.book[1][roll], f32::max);
+                p * best
+            })
+            .sum()
+    }
+
+    /// Upper subtotals reachable by booking the upper categories in `used_upper`, capped at `upper_cap`.
+    fn reachable_upper_scores(&self, used_upper: &[BookingType]) -> Vec<bool> {
+        let mut reachable = vec![false; self.upper_cap as usize + 1];
+        reachable[0] = true;
+        for booking_type in used_upper {
+            let face = UPPER_SECTION.iter().position(|bt| bt == booking_type).unwrap_or(0) as i32 + 1;
+            let mut next = vec![false; reachable.len()];
+            for (upper, _) in reachable.iter().enumerate().filter(|(_, &r)| r) {
+                for count in 0..=DICE as i32 {
+                    next[(upper as i32 + face * count).min(self.upper_cap) as usize] = true;
+                }
+            }
+            reachable = next;
+        }
+        reachable
+    }
+
+    /// Fills the value table, scorecards with fewer open categories first. Every state only depends on states
+    /// with one category less open, so each level is split across threads.
+    fn compute_values(&mut self) {
+        let categories = self.rule_set.categories();
+        let kniffel_index = self.category_index(BookingType::Kniffel);
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+        for open_count in 1..=categories.len() as u32 {
+            let mut states = Vec::new();
+            for open in (0u32..1 << categories.len()).filter(|open| open.count_ones() == open_count) {
+                let used_upper: Vec<BookingType> = categories.iter()
+                    .enumerate()
+                    .filter(|(index, bt)| open & (1 << index) == 0 && bt.is_upper_section())
+                    .map(|(_, &bt)| bt)
+                    .collect();
+                let kniffel_open = kniffel_index.is_none_or(|index| open & (1 << index) != 0);
+                for (upper, _) in self.reachable_upper_scores(&used_upper).iter().enumerate().filter(|(_, &r)| r) {
+                    states.push((open, upper as i32, false));
+                    if self.tracks_kniffel_bonus && !kniffel_open {
+                        states.push((open, upper as i32, true));
+                    }
+                }
+            }
+
+            let chunk_size = states.len().div_ceil(threads).max(1);
+            let solver = &*self;
+            let results: Vec<Vec<(usize, f32)>> = thread::scope(|scope| {
+                let handles: Vec<_> = states.chunks(chunk_size)
+                    .map(|chunk| scope.spawn(move || chunk.iter()
+                        .map(|&(open, upper, flag)| (solver.state_index(open, upper, flag), solver.turn_value(open, upper, flag)))
+                        .collect()))
+                    .collect();
+                handles.into_iter().map(|handle| handle.join().expect("solver thread panicked")).collect()
+            });
+            for (index, value) in results.into_iter().flatten() {
+                self.values[index] = value;
+            }
+        }
+    }
+
+    fn read_values(&self, bytes: &[u8]) -> Option<Vec<f32>> {
+        let payload = bytes.strip_prefix(self.cache_header().as_slice())?;
+        if payload.len() != self.state_count() * 4 {
+            return None;
+        }
+        Some(payload.chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect())
+    }
+
+    fn write_cache(&self, path: &PathBuf) -> std::io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut bytes = self.cache_header();
+        bytes.extend(self.values.iter().flat_map(|value| value.to_le_bytes()));
+        fs::write(path, bytes)
+    }
+
+    /// Expected final score of a new scorecard with optimal play.
+    pub fn expected_game_score(&self) -> f64 {
+        let all_open = (1u32 << self.rule_set.categories().len()) - 1;
+        self.value(all_open, 0, false) as f64
+    }
+
+    /// Every decision the player can take in `position` with the final score it is expected to lead to,
+    /// best first. Keeping all dice is left out, it is never better than booking them.
+    pub fn alternatives(&self, position: &Position) -> Vec<Alternative> {
+        let tables = &self.tables;
+        let roll = match DiceTables::counts(&position.dice).and_then(|counts| tables.roll_index.get(&counts)) {
+            Some(&roll) => roll,
+            None => return Vec::new(),
+        };
+        let score = position.score as f64;
+        let mut alternatives = Vec::new();
+
+        self.for_each_booking(position.open, position.upper_score, position.scored_kniffel, roll, position.served,
+                              |index, points, (next_open, next_upper, next_flag)| {
+            alternatives.push(Alternative {
+                decision: Decision::Book(self.rule_set.categories()[index]),
+                expected_score: score + points as f64 + self.value(next_open, next_upper, next_flag) as f64,
+            });
+        });
+
+        if (1..=2).contains(&position.rolls_left) {
+            let turn = self.turn_tables(position.open, position.upper_score, position.scored_kniffel);
+            let keep_values = &turn.keep[position.rolls_left as usize - 1];
+            for &keep in &tables.roll_keeps[roll] {
+                if tables.keeps[keep].iter().sum::<u8>() as usize == DICE {
+                    continue;
+                }
+                alternatives.push(Alternative {
+                    decision: Decision::Keep(to_dice(&tables.keeps[keep])),
+                    expected_score: score + keep_values[keep] as f64,
+                });
+            }
+        }
+
+        alternatives.sort_by(|a, b| b.expected_score.total_cmp(&a.expected_score));
+        alternatives
+    }
+
+    /// The final score `decision` is expected to lead to in `position` with optimal play afterwards, None if the
+    /// decision is not possible. Keeping all dice counts as a throw that changes nothing.
+    pub fn decision_value(&self, position: &Position, decision: &Decision) -> Option<f64> {
+        let decision = match decision {
+            Decision::Keep(dice_to_keep) => {
+                let mut kept = dice_to_keep.clone();
+                kept.sort();
+                let mut dice = position.dice.clone();
+                dice.sort();
+                if kept == dice && position.rolls_left > 0 {
+                    let next = Position { rolls_left: position.rolls_left - 1, served: false, ..position.clone() };
+                    return self.alternatives(&next).first().map(|alternative| alternative.expected_score);
+                }
+                Decision::Keep(kept)
+            }
+            Decision::Book(booking_type) => Decision::Book(*booking_type),
+        };
+        self.alternatives(position)
+            .into_iter()
+            .find(|alternative| alternative.decision == decision)
+            .map(|alternative| alternative.expected_score)
+    }
+
+    /// The best decision in `position`, None if there is nothing to decide.
+    pub fn best_decision(&self, position: &Position) -> Option<Decision> {
+        self.alternatives(position).into_iter().next().map(|alternative| alternative.decision)
+    }
+
+    /// The position of the current player of `game`. None unless the game is played with a single free
+    /// scorecard column under this solver's rule set and the player can act.
+    pub fn position(&self, game: &KniffelGame) -> Option<Position> {
+        if game.rule_set.name() != self.rule_set.name() || game.column_count != 1 || !game.can_book() {
+            return None;
+        }
+        let column = game.players.get(&game.current_player)?.columns.first()?;
+        if column.order != ColumnOrder::Free {
+            return None;
+        }
+        let open = self.rule_set.categories().iter()
+            .enumerate()
+            .filter(|(_, bt)| !column.has_used_booking_type(bt))
+            .fold(0u32, |mask, (index, _)| mask | 1 << index);
+        Some(Position {
+            open,
+            upper_score: column.upper_score.min(self.upper_cap),
+            scored_kniffel: column.scored_kniffel,
+            score: column.total(),
+            dice: game.dice_rolls.clone(),
+            rolls_left: game.rolls_left().min(MAX_ROLLS_PER_TURN - 1),
+            served: game.roll_round == 1,
+        })
+    }
+}
+
+fn cache_path(rule_set: &dyn RuleSet) -> PathBuf {
+    let directory = env::var("SOLVER_CACHE_DIR").unwrap_or_else(|_| "solver_cache".to_string());
+    PathBuf::from(directory).join(format!("{}.bin", rule_set.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_set::{KNIFFEL, MAXI_YATZY, YAHTZEE};
+
+    fn computed(rule_set: &'static dyn RuleSet) -> Solver {
+        let mut solver = Solver::empty(rule_set);
+        solver.compute_values();
+        solver
+    }
+
+    /// A few categories of KNIFFEL without upper bonus, small enough to compute the value table in a test.
+    #[derive(Debug)]
+    struct Categories {
+        categories: &'static [BookingType],
+        kniffel_points: i32,
+    }
+
+    impl RuleSet for Categories {
+        fn name(&self) -> &'static str {
+            "CATEGORIES"
+        }
+
+        fn categories(&self) -> &'static [BookingType] {
+            self.categories
+        }
+
+        fn score(&self, booking_type: BookingType, dice_rolls: &[i32], served: bool) -> i32 {
+            match booking_type {
+                BookingType::Kniffel if is_kniffel(dice_rolls) => self.kniffel_points,
+                _ => KNIFFEL.score(booking_type, dice_rolls, served),
+            }
+        }
+
+        fn upper_bonus(&self, _upper_score: i32) -> i32 {
+            0
+        }
+    }
+
+    static CHANCE: Categories = Categories { categories: &[BookingType::Chance], kniffel_points: 50 };
+    static KNIFFEL_ONLY: Categories = Categories { categories: &[BookingType::Kniffel], kniffel_points: 50 };
+    static DOUBLE_KNIFFEL: Categories = Categories { categories: &[BookingType::Kniffel], kniffel_points: 100 };
+    static KNIFFEL_AND_CHANCE: Categories =
+        Categories { categories: &[BookingType::Kniffel, BookingType::Chance], kniffel_points: 50 };
+
+    /// Kniffel without extra Kniffel bonuses and joker rules, the variant most published values are given for.
+    #[derive(Debug)]
+    struct PlainKniffel;
+
+    impl RuleSet for PlainKniffel {
+        fn name(&self) -> &'static str {
+            "PLAIN_KNIFFEL"
+        }
+
+        fn categories(&self) -> &'static [BookingType] {
+            KNIFFEL.categories()
+        }
+
+        fn score(&self, booking_type: BookingType, dice_rolls: &[i32], served: bool) -> i32 {
+            KNIFFEL.score(booking_type, dice_rolls, served)
+        }
+
+        fn upper_bonus(&self, upper_score: i32) -> i32 {
+            KNIFFEL.upper_bonus(upper_score)
+        }
+    }
+
+    #[test]
+    fn expected_scores_of_single_turns() {
+        // every die is kept at 5 or 6 after the first throw and at 4 or more after the second, 14/3 points each
+        let chance = computed(&CHANCE);
+        assert!((chance.expected_game_score() - 5.0 * 14.0 / 3.0).abs() < 1e-4, "CHANCE {}", chance.expected_game_score());
+        // a Kniffel is thrown in 4.6% of the turns played for it
+        let kniffel = computed(&KNIFFEL_ONLY);
+        assert!((kniffel.expected_game_score() - 50.0 * 0.04603).abs() < 1e-3, "KNIFFEL {}", kniffel.expected_game_score());
+    }
+
+    #[test]
+    fn best_decision_books_a_kniffel_on_the_table() {
+        let solver = computed(&KNIFFEL_AND_CHANCE);
+        // right after the first throw, both categories open
+        let position = Position {
+            open: 0b11,
+            upper_score: 0,
+            scored_kniffel: false,
+            score: 0,
+            dice: vec![4, 4, 4, 4, 4],
+            rolls_left: 2,
+            served: true,
+        };
+        assert_eq!(solver.best_decision(&position), Some(Decision::Book(BookingType::Kniffel)));
+        let alternatives = solver.alternatives(&position);
+        assert!(alternatives.windows(2).all(|pair| pair[0].expected_score >= pair[1].expected_score));
+        assert_eq!(solver.decision_value(&position, &alternatives[0].decision), Some(alternatives[0].expected_score));
+    }
+
+    #[test]
+    #[ignore = "computes the full value tables, run with cargo test --release -- --ignored"]
+    fn expected_scores_of_optimal_play() {
+        // published values of the optimal solitaire strategy, without and with extra Yahtzee bonuses and jokers
+        let plain = computed(&PlainKniffel);
+        assert!((plain.expected_game_score() - 245.87).abs() < 0.01, "PLAIN_KNIFFEL {}", plain.expected_game_score());
+        let yahtzee = computed(&YAHTZEE);
+        assert!((yahtzee.expected_game_score() - 254.59).abs() < 0.01, "YAHTZEE {}", yahtzee.expected_game_score());
+        // the same with half the extra bonus
+        let kniffel = computed(&KNIFFEL);
+        assert!((kniffel.expected_game_score() - 250.00).abs() < 0.01, "KNIFFEL {}", kniffel.expected_game_score());
+    }
+
+    #[test]
+    fn six_dice_and_saved_rolls_are_not_supported() {
+        assert!(supports(&KNIFFEL));
+        assert!(!supports(&MAXI_YATZY));
+        assert!(solver_for(&MAXI_YATZY).is_none());
+        assert!(ready_solver_for(&MAXI_YATZY).is_none());
+    }
+
+    #[test]
+    fn cache_is_only_read_back_under_the_same_rules() {
+        let kniffel = computed(&KNIFFEL_ONLY);
+        let path = env::temp_dir().join(format!("kniffel-solver-test-{}", std::process::id())).join("CATEGORIES.bin");
+        kniffel.write_cache(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(kniffel.read_values(&bytes), Some(kniffel.values.clone()));
+        // same name and states, only the points of a Kniffel differ
+        let double_kniffel = Solver::empty(&DOUBLE_KNIFFEL);
+        assert_eq!(double_kniffel.state_count(), kniffel.state_count());
+        assert_eq!(double_kniffel.read_values(&bytes), None);
+
+        let mut old_version = bytes.clone();
+        old_version[CACHE_MAGIC.len()] ^= 1;
+        assert_eq!(kniffel.read_values(&old_version), None);
+        assert_eq!(kniffel.read_values(&bytes[..bytes.len() - 4]), None);
+    }
+}